@@ -0,0 +1,112 @@
+use super::{from_f32, AudioInfo, Decoded, Tags};
+use crate::{Error, Result};
+use dasp::Sample;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as CodecError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+// Decodes the default track of any container/codec pair symphonia was built with.
+// Gapless mode is enabled so encoder delay and padding are trimmed from the output.
+pub(crate) fn open<T: Sample>(path: &Path, extension: &str) -> Result<Decoded<T>> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+
+    let format_options = FormatOptions {
+        enable_gapless: true,
+        ..Default::default()
+    };
+
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &format_options,
+        &MetadataOptions::default(),
+    )?;
+
+    let mut tags = Tags::default();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        push_tags(&mut tags, revision);
+    }
+
+    let mut format = probed.format;
+    if let Some(revision) = format.metadata().current() {
+        push_tags(&mut tags, revision);
+    }
+
+    let track = format
+        .default_track()
+        .ok_or(CodecError::Unsupported("no audio track"))?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    if params.codec == CODEC_TYPE_OPUS {
+        return Err(Error::UnsupportedCodec("Opus"));
+    }
+    let mut decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+
+    let mut info = AudioInfo {
+        sample_rate: params.sample_rate.unwrap_or(0),
+        bits_per_sample: params.bits_per_sample.unwrap_or(32) as u16,
+        channels: params.channels.map_or(0, |c| c.count() as u16),
+    };
+
+    let mut samples = Vec::new();
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(CodecError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame is dropped rather than failing the whole file
+            Err(CodecError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let spec = *decoded.spec();
+        info.sample_rate = spec.rate;
+        info.channels = spec.channels.count() as u16;
+
+        let buffer =
+            buffer.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(buffer.samples().iter().map(|s| from_f32::<T>(*s)));
+    }
+
+    Ok(Decoded {
+        info,
+        tags,
+        samples,
+    })
+}
+
+fn push_tags(tags: &mut Tags, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let key = match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => "TITLE",
+            Some(StandardTagKey::Artist) => "ARTIST",
+            Some(StandardTagKey::Album) => "ALBUM",
+            Some(StandardTagKey::AlbumArtist) => "ALBUMARTIST",
+            Some(StandardTagKey::Date) => "DATE",
+            Some(StandardTagKey::Genre) => "GENRE",
+            Some(StandardTagKey::TrackNumber) => "TRACKNUMBER",
+            Some(StandardTagKey::Comment) => "COMMENT",
+            _ => &tag.key,
+        };
+        tags.push(key, &tag.value.to_string());
+    }
+}
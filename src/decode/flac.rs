@@ -0,0 +1,32 @@
+use super::{from_int, AudioInfo, Decoded, Tags};
+use dasp::Sample;
+use std::path::Path;
+
+/// Decodes a FLAC file, keeping its sample rate, bit depth and channel count.
+/// Vorbis comments are returned as track metadata.
+pub fn open<T: Sample, P: AsRef<Path>>(path: P) -> claxon::Result<Decoded<T>> {
+    let mut reader = claxon::FlacReader::open(path)?;
+    let streaminfo = reader.streaminfo();
+    let info = AudioInfo {
+        sample_rate: streaminfo.sample_rate,
+        bits_per_sample: streaminfo.bits_per_sample as u16,
+        channels: streaminfo.channels as u16,
+    };
+
+    let mut tags = Tags::default();
+    for (key, value) in reader.tags() {
+        tags.push(key, value);
+    }
+
+    let mut samples =
+        Vec::with_capacity((streaminfo.samples.unwrap_or(0) * streaminfo.channels as u64) as usize);
+    for sample in reader.samples() {
+        samples.push(from_int(sample?, streaminfo.bits_per_sample));
+    }
+
+    Ok(Decoded {
+        info,
+        tags,
+        samples,
+    })
+}
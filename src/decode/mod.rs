@@ -0,0 +1,122 @@
+mod codec;
+pub mod flac;
+pub mod mp3;
+pub mod ogg;
+pub mod wav;
+
+use crate::{Error, Result};
+use dasp::Sample;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The properties of a decoded audio stream, kept alongside its samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioInfo {
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub channels: u16,
+}
+
+impl Default for AudioInfo {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100,
+            bits_per_sample: 32,
+            channels: 1,
+        }
+    }
+}
+
+/// Textual metadata read from a file, keyed by upper-case Vorbis comment names (`TITLE`, `ARTIST`, ...).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tags(pub Vec<(String, String)>);
+
+impl Tags {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.0.push((key.to_ascii_uppercase(), value.to_string()))
+    }
+}
+
+/// Interleaved samples decoded from a file, converted to the project's sample type.
+pub struct Decoded<T> {
+    pub info: AudioInfo,
+    pub tags: Tags,
+    pub samples: Vec<T>,
+}
+
+/// Decodes a file with the decoder matching its extension.
+pub fn open<T: Sample, P: AsRef<Path>>(path: P) -> Result<Decoded<T>> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .ok_or_else(|| Error::MissingExtension(path.to_path_buf()))?;
+
+    Ok(match extension.to_ascii_lowercase().as_str() {
+        "wav" => wav::open(path)?,
+        "flac" => flac::open(path)?,
+        "mp3" => mp3::open(path)?,
+        "ogg" => ogg::open(path)?,
+        _ => return Err(Error::UnsupportedFormat(extension.to_string())),
+    })
+}
+
+impl<T: Copy> Decoded<T> {
+    /// Splits the interleaved samples into one buffer per channel.
+    pub fn deinterleave(&self) -> Vec<Vec<T>> {
+        let channels = self.info.channels.max(1) as usize;
+        (0..channels)
+            .map(|channel| {
+                self.samples
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// A short name for channel `channel` of `channels`, following the usual WAV speaker order.
+pub fn channel_label(channel: usize, channels: usize) -> String {
+    match (channels, channel) {
+        (2, 0) => String::from("L"),
+        (2, 1) => String::from("R"),
+        _ => format!("{}", channel + 1),
+    }
+}
+
+pub(crate) fn from_f32<T: Sample>(sample: f32) -> T {
+    T::Float::from_sample(sample).to_sample::<T>()
+}
+
+// Converts a signed integer sample with `bits` significant bits (e.g. a 24-bit sample stored in an
+// `i32`) into `T`. It is first shifted up to fill an `i32`, so any bit depth converts exactly.
+pub(crate) fn from_int<T: Sample>(sample: i32, bits: u32) -> T {
+    let full_scale = sample << (32 - bits.clamp(1, 32));
+    T::Float::from_sample(full_scale.to_sample::<f64>()).to_sample::<T>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_of_any_depth_convert_to_full_scale() {
+        assert_eq!(from_int::<f32>(64, 8), 0.5);
+        assert_eq!(from_int::<f32>(-8_388_608, 24), -1.0);
+        assert_eq!(from_int::<f32>(0x20_0000, 24), 0.25);
+        assert_eq!(from_int::<i16>(0x7f_ff00, 24), i16::MAX);
+        assert_eq!(from_int::<i16>(-32768, 16), i16::MIN);
+        assert_eq!(from_int::<i32>(-1, 1), i32::MIN);
+        assert_eq!(from_int::<f32>(0, 32), 0.0);
+    }
+}
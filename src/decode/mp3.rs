@@ -0,0 +1,10 @@
+use super::{codec, Decoded};
+use crate::Result;
+use dasp::Sample;
+use std::path::Path;
+
+/// Decodes an MP3 file, trimming the encoder delay and padding recorded in its LAME/Xing header
+/// so the samples line up with the source material. ID3 tags are returned as track metadata.
+pub fn open<T: Sample, P: AsRef<Path>>(path: P) -> Result<Decoded<T>> {
+    codec::open(path.as_ref(), "mp3")
+}
@@ -0,0 +1,71 @@
+use super::{codec, Decoded};
+use crate::Result;
+use dasp::Sample;
+use std::path::Path;
+
+/// Decodes an Ogg Vorbis file with its sample rate and channel layout. Vorbis comments are
+/// returned as track metadata.
+///
+/// Ogg Opus streams are recognised by the demuxer, but there is no Opus decoder available yet,
+/// so they fail with `Error::UnsupportedCodec`.
+pub fn open<T: Sample, P: AsRef<Path>>(path: P) -> Result<Decoded<T>> {
+    codec::open(path.as_ref(), "ogg")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::fs;
+
+    // An Ogg page of one stream holding `packet`, which must be shorter than 255 bytes.
+    fn page(header_type: u8, granule: u64, sequence: u32, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.extend([0, header_type]);
+        page.extend(granule.to_le_bytes());
+        page.extend(1u32.to_le_bytes());
+        page.extend(sequence.to_le_bytes());
+        page.extend([0; 4]);
+        page.extend([1, packet.len() as u8]);
+        page.extend(packet);
+
+        let mut crc = 0u32;
+        for byte in page.iter() {
+            crc ^= (*byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04c1_1db7
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    #[test]
+    fn opus_is_reported_as_unsupported() {
+        let mut head = b"OpusHead".to_vec();
+        head.extend([1, 1, 0x38, 0x01, 0x80, 0xbb, 0, 0, 0, 0, 0]);
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend(0u32.to_le_bytes());
+        tags.extend(0u32.to_le_bytes());
+        // A header page, a comment page and a last page of one silent 20 ms frame
+        let mut file = page(2, 0, 0, &head);
+        file.extend(page(0, 0, 1, &tags));
+        file.extend(page(4, 960, 2, &[0xf8, 0xff, 0xfe]));
+
+        let path = std::env::temp_dir().join(format!("impulse-editor-{}.ogg", std::process::id()));
+        fs::write(&path, file).unwrap();
+        let result = open::<f32, _>(&path);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(e @ Error::UnsupportedCodec(_)) => {
+                assert_eq!(e.to_string(), "Unsupported codec: Opus")
+            }
+            Err(e) => panic!("{}", e),
+            Ok(_) => panic!("decoded Opus"),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use super::{from_f32, from_int, AudioInfo, Decoded, Tags};
+use dasp::Sample;
+use hound::{SampleFormat, WavReader};
+use std::path::Path;
+
+/// Decodes a WAV file, converting integer PCM of any bit depth or 32-bit float into `T`.
+pub fn open<T: Sample, P: AsRef<Path>>(path: P) -> hound::Result<Decoded<T>> {
+    let reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let info = AudioInfo {
+        sample_rate: spec.sample_rate,
+        bits_per_sample: spec.bits_per_sample,
+        channels: spec.channels,
+    };
+
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .map(|s| s.map(from_f32))
+            .collect::<hound::Result<Vec<T>>>()?,
+        SampleFormat::Int => {
+            let bits = spec.bits_per_sample as u32;
+            reader
+                .into_samples::<i32>()
+                .map(|s| s.map(|s| from_int(s, bits)))
+                .collect::<hound::Result<Vec<T>>>()?
+        }
+    };
+
+    Ok(Decoded {
+        info,
+        tags: Tags::default(),
+        samples,
+    })
+}
@@ -0,0 +1,62 @@
+use std::f64::consts::PI;
+
+/// A second-order IIR filter section in transposed direct form II.
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    /// A filter with the given feedforward (`b`) and feedback (`a`) coefficients, normalised by
+    /// `a[0]`.
+    pub fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            state: [0.0; 2],
+        }
+    }
+
+    /// A resonant low-pass filter, after the RBJ cookbook.
+    pub fn low_pass(cutoff: f64, q: f64, sample_rate: u32) -> Self {
+        let (cos, alpha) = prewarp(cutoff, q, sample_rate);
+        Self::new(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// A resonant high-pass filter, after the RBJ cookbook.
+    pub fn high_pass(cutoff: f64, q: f64, sample_rate: u32) -> Self {
+        let (cos, alpha) = prewarp(cutoff, q, sample_rate);
+        Self::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Takes on the coefficients of `other`, keeping what this filter holds from earlier samples,
+    /// so a running filter can be retuned without a click.
+    pub fn retune(&mut self, other: &Biquad) {
+        self.b = other.b;
+        self.a = other.a;
+    }
+
+    pub fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.state[0];
+        self.state[0] = self.b[1] * input - self.a[0] * output + self.state[1];
+        self.state[1] = self.b[2] * input - self.a[1] * output;
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.state = [0.0; 2];
+    }
+}
+
+fn prewarp(cutoff: f64, q: f64, sample_rate: u32) -> (f64, f64) {
+    let omega = 2.0 * PI * cutoff / sample_rate as f64;
+    (omega.cos(), omega.sin() / (2.0 * q))
+}
@@ -0,0 +1,235 @@
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::sync::Arc;
+
+/// The block size `convolve` works in. Larger blocks mean fewer, bigger transforms, which is
+/// faster when latency doesn't matter.
+const OFFLINE_BLOCK_SIZE: usize = 4096;
+
+/// Convolves a signal with an impulse response in blocks, so even impulse responses several
+/// seconds long can run in real time.
+///
+/// The impulse response is cut into partitions of one block each, which are transformed once up
+/// front. Every block of input is transformed once too, and its spectrum is kept for as many
+/// blocks as there are partitions, so each output block is a single inverse transform of the sum
+/// of the partitions multiplied by the input spectra they line up with (uniformly partitioned
+/// overlap-save). The output lags the input by one block.
+#[derive(Clone)]
+pub struct Convolver {
+    block_size: usize,
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    scratch: Vec<Complex<f32>>,
+    // The spectrum of each partition of the impulse response, from DC up to Nyquist.
+    partitions: Vec<Vec<Complex<f32>>>,
+    // The spectra of the latest input blocks, as a ring with the newest at `head`.
+    spectra: Vec<Vec<Complex<f32>>>,
+    head: usize,
+    // The previous input block, followed by the one being filled.
+    input: Vec<f32>,
+    // The output block being played out while the next input block fills.
+    output: Vec<f32>,
+    fill: usize,
+    buffer: Vec<Complex<f32>>,
+    sum: Vec<Complex<f32>>,
+}
+
+impl Convolver {
+    /// A convolver for `impulse`, working in blocks of `block_size` samples rounded up to a power
+    /// of two.
+    pub fn new(impulse: &[f32], block_size: usize) -> Self {
+        let block_size = block_size.max(1).next_power_of_two();
+        let fft_size = block_size * 2;
+        let bins = block_size + 1;
+
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        let ifft = planner.plan_fft_inverse(fft_size);
+        let scratch_len = fft
+            .get_inplace_scratch_len()
+            .max(ifft.get_inplace_scratch_len());
+        let mut scratch = vec![Complex::default(); scratch_len];
+
+        let mut buffer = vec![Complex::default(); fft_size];
+        let partitions: Vec<Vec<Complex<f32>>> = impulse
+            .chunks(block_size)
+            .map(|partition| {
+                for (i, slot) in buffer.iter_mut().enumerate() {
+                    *slot = Complex::new(partition.get(i).copied().unwrap_or(0.0), 0.0);
+                }
+                fft.process_with_scratch(&mut buffer, &mut scratch);
+                buffer[..bins].to_vec()
+            })
+            .collect();
+
+        Self {
+            block_size,
+            spectra: vec![vec![Complex::default(); bins]; partitions.len()],
+            partitions,
+            fft,
+            ifft,
+            scratch,
+            head: 0,
+            input: vec![0.0; fft_size],
+            output: vec![0.0; block_size],
+            fill: 0,
+            buffer,
+            sum: vec![Complex::default(); bins],
+        }
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// How many samples the output lags behind the input.
+    pub fn latency(&self) -> usize {
+        self.block_size
+    }
+
+    /// Convolves a block of any length in place.
+    pub fn process(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            self.input[self.block_size + self.fill] = *sample;
+            *sample = self.output[self.fill];
+            self.fill += 1;
+            if self.fill == self.block_size {
+                self.fill = 0;
+                self.convolve_block();
+            }
+        }
+    }
+
+    /// Forgets every input sample so far, as after a seek.
+    pub fn reset(&mut self) {
+        for spectrum in self.spectra.iter_mut() {
+            for bin in spectrum.iter_mut() {
+                *bin = Complex::default();
+            }
+        }
+        for sample in self.input.iter_mut().chain(self.output.iter_mut()) {
+            *sample = 0.0;
+        }
+        self.head = 0;
+        self.fill = 0;
+    }
+
+    // Turns the input block that was just filled into the next output block.
+    fn convolve_block(&mut self) {
+        let block_size = self.block_size;
+        let fft_size = block_size * 2;
+        let bins = block_size + 1;
+        let count = self.partitions.len();
+        if count == 0 {
+            for sample in self.output.iter_mut() {
+                *sample = 0.0;
+            }
+            return;
+        }
+
+        for (slot, sample) in self.buffer.iter_mut().zip(self.input.iter()) {
+            *slot = Complex::new(*sample, 0.0);
+        }
+        self.fft
+            .process_with_scratch(&mut self.buffer, &mut self.scratch);
+        self.spectra[self.head].copy_from_slice(&self.buffer[..bins]);
+
+        // Partition k lines up with the input from k blocks ago
+        for bin in self.sum.iter_mut() {
+            *bin = Complex::default();
+        }
+        for (k, partition) in self.partitions.iter().enumerate() {
+            let spectrum = &self.spectra[(self.head + count - k) % count];
+            for ((sum, a), b) in self.sum.iter_mut().zip(spectrum).zip(partition) {
+                *sum += a * b;
+            }
+        }
+
+        // The signal is real, so the upper half of the spectrum mirrors the lower half
+        self.buffer[..bins].copy_from_slice(&self.sum);
+        for i in 1..block_size {
+            self.buffer[fft_size - i] = self.sum[i].conj();
+        }
+        self.ifft
+            .process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+        // The first half wraps around, and only the second half is the true convolution
+        let scale = 1.0 / fft_size as f32;
+        for (out, slot) in self.output.iter_mut().zip(&self.buffer[block_size..]) {
+            *out = slot.re * scale;
+        }
+
+        self.input.copy_within(block_size.., 0);
+        self.head = (self.head + 1) % count;
+    }
+}
+
+/// The full convolution of `signal` with `impulse`, which is as long as both together less one.
+pub fn convolve(signal: &[f32], impulse: &[f32]) -> Vec<f32> {
+    if signal.is_empty() || impulse.is_empty() {
+        return vec![];
+    }
+
+    let len = signal.len() + impulse.len() - 1;
+    let mut convolver = Convolver::new(impulse, OFFLINE_BLOCK_SIZE);
+    let latency = convolver.latency();
+
+    let mut out = signal.to_vec();
+    out.resize(len + latency, 0.0);
+    convolver.process(&mut out);
+    out.drain(..latency);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direct(signal: &[f32], impulse: &[f32]) -> Vec<f32> {
+        let mut out = vec![0.0; signal.len() + impulse.len() - 1];
+        for (i, s) in signal.iter().enumerate() {
+            for (j, h) in impulse.iter().enumerate() {
+                out[i + j] += s * h;
+            }
+        }
+        out
+    }
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            assert!((a - b).abs() < 1e-3, "{} != {} at {}", a, b, i);
+        }
+    }
+
+    #[test]
+    fn convolve_matches_direct_convolution() {
+        // Long enough for the impulse to span several partitions
+        let signal: Vec<f32> = (0..10000).map(|i| (i as f32 * 0.37).sin()).collect();
+        let impulse: Vec<f32> = (0..9000)
+            .map(|i| (i as f32 * 0.11).cos() * (-(i as f32) / 2000.0).exp())
+            .collect();
+        assert_close(&convolve(&signal, &impulse), &direct(&signal, &impulse));
+        assert_close(&convolve(&[0.5], &[1.0, -1.0]), &[0.5, -0.5]);
+        assert!(convolve(&[], &impulse).is_empty());
+    }
+
+    #[test]
+    fn convolver_matches_direct_convolution_in_uneven_blocks() {
+        let signal: Vec<f32> = (0..3000).map(|i| ((i * 7919) % 13) as f32 / 13.0).collect();
+        let impulse: Vec<f32> = (0..700).map(|i| 1.0 / (i + 1) as f32).collect();
+        let mut convolver = Convolver::new(&impulse, 64);
+
+        let mut out = signal.clone();
+        out.resize(signal.len() + impulse.len() - 1 + convolver.latency(), 0.0);
+        let mut start = 0;
+        for len in [1, 63, 64, 65, 200].iter().cycle() {
+            let end = (start + len).min(out.len());
+            convolver.process(&mut out[start..end]);
+            start = end;
+            if start == out.len() {
+                break;
+            }
+        }
+        assert_close(&out[convolver.latency()..], &direct(&signal, &impulse));
+    }
+}
@@ -0,0 +1,125 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::f32::consts::PI;
+use std::fmt;
+
+/// How much is kept before the onset when the pre-delay is trimmed, in seconds, so the rise of
+/// the direct sound isn't cut into.
+pub const PRE_DELAY_MARGIN: f32 = 0.001;
+
+/// The sample where the direct sound arrives: the first one within 20 dB of the peak, as in
+/// ISO 3382-1. There is none in silence.
+pub fn onset(samples: &[f32]) -> Option<usize> {
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    if peak > 0.0 {
+        samples.iter().position(|s| s.abs() >= peak * 0.1)
+    } else {
+        None
+    }
+}
+
+/// A one-step clean-up of an impulse response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preparation {
+    /// Removes the silence before the onset, less `PRE_DELAY_MARGIN`.
+    TrimPreDelay,
+    /// Fades the last `length` seconds out along a half cosine.
+    FadeTail { length: f32 },
+    /// Scales the peak to `peak_db` dBFS.
+    Normalize { peak_db: f32 },
+    /// Keeps the magnitude response but gives it the least possible delay, so the energy is
+    /// packed at the start.
+    MinimumPhase,
+}
+
+impl Preparation {
+    /// The impulse response `samples`, recorded at `sample_rate`, after this step.
+    pub fn apply(&self, samples: &[f32], sample_rate: u32) -> Vec<f32> {
+        match *self {
+            Preparation::TrimPreDelay => {
+                let margin = (PRE_DELAY_MARGIN * sample_rate as f32) as usize;
+                let start = onset(samples).map_or(0, |onset| onset.saturating_sub(margin));
+                samples[start..].to_vec()
+            }
+            Preparation::FadeTail { length } => {
+                let mut samples = samples.to_vec();
+                let len = samples.len();
+                let fade = ((length.max(0.0) * sample_rate as f32) as usize).min(len);
+                for (i, sample) in samples[len - fade..].iter_mut().enumerate() {
+                    *sample *= 0.5 + 0.5 * (PI * (i + 1) as f32 / fade as f32).cos();
+                }
+                samples
+            }
+            Preparation::Normalize { peak_db } => {
+                let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+                let scale = if peak > 0.0 {
+                    10f32.powf(peak_db / 20.0) / peak
+                } else {
+                    1.0
+                };
+                samples.iter().map(|s| s * scale).collect()
+            }
+            Preparation::MinimumPhase => minimum_phase(samples),
+        }
+    }
+}
+
+impl fmt::Display for Preparation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Preparation::TrimPreDelay => "Trim pre-delay",
+            Preparation::FadeTail { .. } => "Fade tail",
+            Preparation::Normalize { .. } => "Normalize",
+            Preparation::MinimumPhase => "Minimum phase",
+        })
+    }
+}
+
+/// The minimum phase version of `samples`, as long as the original, found through the real
+/// cepstrum. Magnitudes more than 100 dB below the peak are raised to that floor, since the
+/// logarithm of nothing is undefined.
+pub fn minimum_phase(samples: &[f32]) -> Vec<f32> {
+    if samples.is_empty() {
+        return vec![];
+    }
+
+    // Padding well past the length keeps the cepstrum from wrapping around onto itself
+    let size = (samples.len() * 4).next_power_of_two();
+    let mut planner = FftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(size);
+    let ifft = planner.plan_fft_inverse(size);
+    let scale = 1.0 / size as f32;
+
+    let mut buffer: Vec<Complex<f32>> = (0..size)
+        .map(|i| Complex::new(samples.get(i).copied().unwrap_or(0.0), 0.0))
+        .collect();
+    fft.process(&mut buffer);
+
+    let peak = buffer.iter().fold(0.0f32, |peak, c| peak.max(c.norm()));
+    let floor = (peak * 1e-5).max(f32::MIN_POSITIVE);
+    for c in buffer.iter_mut() {
+        *c = Complex::new(c.norm().max(floor).ln(), 0.0);
+    }
+    ifft.process(&mut buffer);
+
+    // Folding the anticausal half of the cepstrum onto the causal half makes it minimum phase
+    for (i, c) in buffer.iter_mut().enumerate() {
+        let weight = if i == 0 || i == size / 2 {
+            1.0
+        } else if i < size / 2 {
+            2.0
+        } else {
+            0.0
+        };
+        *c = Complex::new(c.re * scale * weight, 0.0);
+    }
+    fft.process(&mut buffer);
+    for c in buffer.iter_mut() {
+        *c = c.exp();
+    }
+    ifft.process(&mut buffer);
+
+    buffer[..samples.len()]
+        .iter()
+        .map(|c| c.re * scale)
+        .collect()
+}
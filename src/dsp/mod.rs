@@ -0,0 +1,13 @@
+pub mod biquad;
+pub mod convolution;
+pub mod impulse;
+pub mod resample;
+pub mod stft;
+pub mod window;
+
+pub use biquad::Biquad;
+pub use convolution::{convolve, Convolver};
+pub use impulse::Preparation;
+pub use resample::resample;
+pub use stft::Stft;
+pub use window::Window;
@@ -0,0 +1,23 @@
+use dasp::Sample;
+
+/// Converts mono `samples` from one sample rate to another by linear interpolation, the same way
+/// the playback mixer reads sources at other rates.
+pub fn resample<T: Sample>(samples: &[T], from: u32, to: u32) -> Vec<T> {
+    if from == to || from == 0 || to == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    let step = from as f64 / to as f64;
+    (0..len)
+        .map(|i| {
+            let time = i as f64 * step;
+            let index = time as usize;
+            let a = samples[index].to_float_sample().to_sample::<f64>();
+            let b = samples
+                .get(index + 1)
+                .map_or(a, |b| b.to_float_sample().to_sample::<f64>());
+            T::Float::from_sample(a + (b - a) * (time - index as f64)).to_sample::<T>()
+        })
+        .collect()
+}
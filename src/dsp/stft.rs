@@ -0,0 +1,86 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// The magnitude spectrum of a signal over time, in decibels relative to a full-scale sine.
+#[derive(Debug, Clone, Default)]
+pub struct Stft {
+    pub fft_size: usize,
+    pub hop_size: usize,
+    /// One spectrum of `fft_size / 2 + 1` bins per hop, from DC up to Nyquist.
+    pub frames: Vec<Vec<f32>>,
+}
+
+impl Stft {
+    /// Runs a short-time Fourier transform over `samples`, weighting each frame by `window`
+    /// (which must be `fft_size` long). The signal is zero-padded so every sample is analysed.
+    pub fn new(samples: &[f32], window: &[f32], hop_size: usize) -> Self {
+        let mut stft = Self {
+            fft_size: window.len(),
+            hop_size: hop_size.max(1),
+            frames: vec![],
+        };
+        stft.extend(samples, window);
+        stft
+    }
+
+    /// Drops the frames that reach past the first `unchanged` samples of the signal, as when
+    /// more are appended, and returns the sample the next frame starts at.
+    pub fn truncate(&mut self, unchanged: usize) -> usize {
+        let complete = if unchanged >= self.fft_size {
+            (unchanged - self.fft_size) / self.hop_size.max(1) + 1
+        } else {
+            0
+        };
+        self.frames.truncate(complete);
+        self.frames.len() * self.hop_size
+    }
+
+    /// Analyses the signal from where the frames so far end, given the samples from there on.
+    pub fn extend(&mut self, samples: &[f32], window: &[f32]) {
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(self.fft_size);
+        let mut scratch = vec![Complex::default(); fft.get_inplace_scratch_len()];
+        let mut buffer = vec![Complex::default(); self.fft_size];
+
+        // A full-scale sine peaks at half the window's sum
+        let gain = 2.0 / window.iter().sum::<f32>();
+        let bins = self.bins();
+
+        let frames = (0..samples.len()).step_by(self.hop_size).map(|start| {
+            for (i, slot) in buffer.iter_mut().enumerate() {
+                let sample = samples.get(start + i).copied().unwrap_or(0.0);
+                *slot = Complex::new(sample * window[i], 0.0);
+            }
+            fft.process_with_scratch(&mut buffer, &mut scratch);
+            buffer[..bins]
+                .iter()
+                .map(|c| 20.0 * (c.norm() * gain).max(f32::MIN_POSITIVE).log10())
+                .collect()
+        });
+        self.frames.extend(frames);
+    }
+
+    pub fn bins(&self) -> usize {
+        self.fft_size / 2 + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::Window;
+
+    #[test]
+    fn extending_matches_analysing_at_once() {
+        let samples: Vec<f32> = (0..5000).map(|i| (i as f32 * 0.05).sin()).collect();
+        let window = Window::Hann.coefficients(512);
+        let whole = Stft::new(&samples, &window, 128);
+
+        let mut grown = Stft::new(&samples[..3000], &window, 128);
+        let start = grown.truncate(3000);
+        grown.extend(&samples[start..], &window);
+
+        assert_eq!(grown.frames.len(), whole.frames.len());
+        for (a, b) in grown.frames.iter().zip(whole.frames.iter()) {
+            assert_eq!(a, b);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::fmt;
+
+// The shape parameter used for `Window::Kaiser`, giving sidelobes around -90 dB.
+const KAISER_BETA: f32 = 9.0;
+
+/// A window function to taper each analysis frame with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Window {
+    #[default]
+    Hann,
+    Hamming,
+    BlackmanHarris,
+    Kaiser,
+}
+
+impl Window {
+    pub const ALL: [Window; 4] = [
+        Window::Hann,
+        Window::Hamming,
+        Window::BlackmanHarris,
+        Window::Kaiser,
+    ];
+
+    /// The periodic window of `size` points.
+    pub fn coefficients(&self, size: usize) -> Vec<f32> {
+        match self {
+            Window::Hann => hann(size),
+            Window::Hamming => hamming(size),
+            Window::BlackmanHarris => blackman_harris(size),
+            Window::Kaiser => kaiser(size, KAISER_BETA),
+        }
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Window::Hann => "Hann",
+            Window::Hamming => "Hamming",
+            Window::BlackmanHarris => "Blackman-Harris",
+            Window::Kaiser => "Kaiser",
+        })
+    }
+}
+
+/// A periodic Hann window of `size` points.
+pub fn hann(size: usize) -> Vec<f32> {
+    cosine_sum(size, &[0.5, 0.5])
+}
+
+/// A periodic Hamming window of `size` points.
+pub fn hamming(size: usize) -> Vec<f32> {
+    cosine_sum(size, &[0.54, 0.46])
+}
+
+/// A periodic 4-term Blackman-Harris window of `size` points.
+pub fn blackman_harris(size: usize) -> Vec<f32> {
+    cosine_sum(size, &[0.35875, 0.48829, 0.14128, 0.01168])
+}
+
+/// A periodic Kaiser window of `size` points with shape parameter `beta`.
+pub fn kaiser(size: usize, beta: f32) -> Vec<f32> {
+    let denominator = bessel_i0(beta);
+    (0..size)
+        .map(|n| {
+            let x = 2.0 * n as f32 / size as f32 - 1.0;
+            bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / denominator
+        })
+        .collect()
+}
+
+// a0 - a1 cos(2πn/N) + a2 cos(4πn/N) - a3 cos(6πn/N) ...
+fn cosine_sum(size: usize, coefficients: &[f32]) -> Vec<f32> {
+    (0..size)
+        .map(|n| {
+            let phase = 2.0 * PI * n as f32 / size as f32;
+            coefficients
+                .iter()
+                .enumerate()
+                .map(|(k, a)| if k % 2 == 0 { 1.0 } else { -1.0 } * a * (k as f32 * phase).cos())
+                .sum()
+        })
+        .collect()
+}
+
+// The zeroth-order modified Bessel function of the first kind, by its power series.
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..50 {
+        term *= (half / k as f32).powi(2);
+        sum += term;
+        if term < sum * 1e-9 {
+            break;
+        }
+    }
+    sum
+}
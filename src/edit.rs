@@ -0,0 +1,519 @@
+use crate::dsp;
+use crate::effect::Effect;
+use crate::history::Command;
+use crate::playback::Strip;
+use crate::project::SourceRef;
+use crate::track::{Channel, Clip, Selection, Tracks};
+use crate::widgets::spectrogram::Settings;
+use crate::widgets::Spectrogram;
+use dasp::Sample;
+use std::ops::Range;
+
+/// An undoable change to a session's tracks.
+pub enum Edit<T> {
+    /// Adds tracks at `index`. `stash` holds them whenever they aren't in the session.
+    InsertTracks {
+        index: usize,
+        count: usize,
+        stash: Vec<(Channel<T>, Spectrogram<T>)>,
+    },
+    /// Removes `count` tracks from `index`. `stash` holds them while they are removed.
+    RemoveTracks {
+        index: usize,
+        count: usize,
+        stash: Vec<(Channel<T>, Spectrogram<T>)>,
+    },
+    /// Replaces `removed`, starting at sample `start` of track `track`, with `inserted`.
+    Splice {
+        track: usize,
+        start: usize,
+        removed: Vec<T>,
+        inserted: Vec<T>,
+        // The track's file source while it is swapped out, since edited samples no longer match it.
+        source: Option<SourceRef>,
+    },
+    /// Changes the mixer settings of track `track`.
+    Strip {
+        track: usize,
+        before: Strip,
+        after: Strip,
+    },
+    /// Adds an effect to the chain of track `track`. `stash` holds it whenever it isn't in the
+    /// chain.
+    InsertEffect {
+        track: usize,
+        index: usize,
+        stash: Option<Box<dyn Effect>>,
+    },
+    /// Removes an effect from the chain of track `track`. `stash` holds it while it is removed.
+    RemoveEffect {
+        track: usize,
+        index: usize,
+        stash: Option<Box<dyn Effect>>,
+    },
+    /// Moves an effect of track `track` from one place in its chain to another.
+    MoveEffect {
+        track: usize,
+        from: usize,
+        to: usize,
+    },
+    /// Changes a parameter of effect `effect` of track `track`.
+    EffectParameter {
+        track: usize,
+        effect: usize,
+        parameter: usize,
+        before: f32,
+        after: f32,
+    },
+    /// Changes the spectrogram settings of track `track`.
+    Spectrogram {
+        track: usize,
+        before: Settings,
+        after: Settings,
+    },
+}
+
+impl<T> Edit<T>
+where
+    T: Sample,
+{
+    pub fn insert_tracks(index: usize, tracks: Vec<(Channel<T>, Spectrogram<T>)>) -> Self {
+        Edit::InsertTracks {
+            index,
+            count: tracks.len(),
+            stash: tracks,
+        }
+    }
+
+    pub fn remove_tracks(index: usize, count: usize) -> Self {
+        Edit::RemoveTracks {
+            index,
+            count,
+            stash: vec![],
+        }
+    }
+
+    /// Replaces the samples in `range` of track `track` with `inserted`. The range is clamped to
+    /// the track's length.
+    pub fn splice(tracks: &Tracks<T>, track: usize, range: Range<usize>, inserted: Vec<T>) -> Self {
+        let (start, removed) = match tracks.channels.get(track) {
+            Some(channel) => {
+                let samples = channel.samples.lock().unwrap();
+                let end = range.end.min(samples.len());
+                let start = range.start.min(end);
+                (start, samples[start..end].to_vec())
+            }
+            None => (0, vec![]),
+        };
+        Edit::Splice {
+            track,
+            start,
+            removed,
+            inserted,
+            source: None,
+        }
+    }
+
+    /// Removes the selected samples.
+    pub fn delete(tracks: &Tracks<T>, selection: &Selection) -> Self {
+        Self::splice(tracks, selection.track, selection.range.clone(), vec![])
+    }
+
+    /// Removes everything but the selected samples.
+    pub fn trim(tracks: &Tracks<T>, selection: &Selection) -> Self {
+        let kept = tracks
+            .copy(selection)
+            .map_or_else(Vec::new, |clip| clip.samples);
+        Self::splice(tracks, selection.track, 0..usize::MAX, kept)
+    }
+
+    /// Inserts `len` samples of silence at sample `at` of track `track`.
+    pub fn insert_silence(tracks: &Tracks<T>, track: usize, at: usize, len: usize) -> Self {
+        Self::splice(tracks, track, at..at, vec![T::EQUILIBRIUM; len])
+    }
+
+    /// Replaces the selected samples with `clip`, resampled to the track's sample rate.
+    pub fn paste(tracks: &Tracks<T>, selection: &Selection, clip: &Clip<T>) -> Self {
+        let samples = match tracks.channels.get(selection.track) {
+            Some(channel) => dsp::resample(
+                &clip.samples,
+                clip.info.sample_rate,
+                channel.info.sample_rate,
+            ),
+            None => vec![],
+        };
+        Self::splice(tracks, selection.track, selection.range.clone(), samples)
+    }
+
+    /// Whether this edit leaves track `recording` where it is, with its samples untouched, so it
+    /// can be made while that track is being recorded into.
+    pub fn allowed_while_recording(&self, recording: usize) -> bool {
+        match self {
+            Edit::InsertTracks { index, .. } | Edit::RemoveTracks { index, .. } => {
+                *index > recording
+            }
+            Edit::Splice { track, .. } => *track != recording,
+            _ => true,
+        }
+    }
+
+    /// The samples this edit covers once applied, for selecting them afterwards.
+    pub fn applied_selection(&self) -> Option<Selection> {
+        match self {
+            Edit::Splice {
+                track,
+                start,
+                inserted,
+                ..
+            } => Some(Selection {
+                track: *track,
+                range: *start..*start + inserted.len(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<T> Command<Tracks<T>> for Edit<T>
+where
+    T: Sample,
+{
+    fn apply(&mut self, tracks: &mut Tracks<T>) {
+        match self {
+            Edit::InsertTracks { index, stash, .. } => tracks.put(*index, std::mem::take(stash)),
+            Edit::RemoveTracks {
+                index,
+                count,
+                stash,
+            } => *stash = tracks.take(*index, *count),
+            Edit::Splice {
+                track,
+                start,
+                removed,
+                inserted,
+                source,
+            } => splice(tracks, *track, *start, removed.len(), inserted, source),
+            Edit::Strip { track, after, .. } => set_strip(tracks, *track, *after),
+            Edit::InsertEffect {
+                track,
+                index,
+                stash,
+            } => insert_effect(tracks, *track, *index, stash),
+            Edit::RemoveEffect {
+                track,
+                index,
+                stash,
+            } => *stash = remove_effect(tracks, *track, *index),
+            Edit::MoveEffect { track, from, to } => move_effect(tracks, *track, *from, *to),
+            Edit::EffectParameter {
+                track,
+                effect,
+                parameter,
+                after,
+                ..
+            } => set_parameter(tracks, *track, *effect, *parameter, *after),
+            Edit::Spectrogram { track, after, .. } => set_settings(tracks, *track, *after),
+        }
+    }
+
+    fn revert(&mut self, tracks: &mut Tracks<T>) {
+        match self {
+            Edit::InsertTracks {
+                index,
+                count,
+                stash,
+            } => *stash = tracks.take(*index, *count),
+            Edit::RemoveTracks { index, stash, .. } => tracks.put(*index, std::mem::take(stash)),
+            Edit::Splice {
+                track,
+                start,
+                removed,
+                inserted,
+                source,
+            } => splice(tracks, *track, *start, inserted.len(), removed, source),
+            Edit::Strip { track, before, .. } => set_strip(tracks, *track, *before),
+            Edit::InsertEffect {
+                track,
+                index,
+                stash,
+            } => *stash = remove_effect(tracks, *track, *index),
+            Edit::RemoveEffect {
+                track,
+                index,
+                stash,
+            } => insert_effect(tracks, *track, *index, stash),
+            Edit::MoveEffect { track, from, to } => move_effect(tracks, *track, *to, *from),
+            Edit::EffectParameter {
+                track,
+                effect,
+                parameter,
+                before,
+                ..
+            } => set_parameter(tracks, *track, *effect, *parameter, *before),
+            Edit::Spectrogram { track, before, .. } => set_settings(tracks, *track, *before),
+        }
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (
+                Edit::Spectrogram {
+                    track,
+                    before,
+                    after,
+                },
+                Edit::Spectrogram {
+                    track: next_track,
+                    before: next_before,
+                    after: next_after,
+                },
+            ) if track == next_track
+                && changed_fields(before, after) == changed_fields(next_before, next_after) =>
+            {
+                *after = *next_after;
+                true
+            }
+            // Only continuous controls are merged, so each toggle can be undone on its own
+            (
+                Edit::Strip {
+                    track,
+                    before,
+                    after,
+                },
+                Edit::Strip {
+                    track: next_track,
+                    before: next_before,
+                    after: next_after,
+                },
+            ) if track == next_track
+                && changed_strip_fields(before, after)
+                    == changed_strip_fields(next_before, next_after)
+                && before.pan_law == after.pan_law
+                && before.mute == after.mute
+                && before.solo == after.solo =>
+            {
+                *after = *next_after;
+                true
+            }
+            (
+                Edit::EffectParameter {
+                    track,
+                    effect,
+                    parameter,
+                    after,
+                    ..
+                },
+                Edit::EffectParameter {
+                    track: next_track,
+                    effect: next_effect,
+                    parameter: next_parameter,
+                    after: next_after,
+                    ..
+                },
+            ) if (*track, *effect, *parameter) == (*next_track, *next_effect, *next_parameter) => {
+                *after = *next_after;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Replaces `len` samples from `start` with `samples`, swapping the track's source with `source`.
+fn splice<T: Sample>(
+    tracks: &mut Tracks<T>,
+    track: usize,
+    start: usize,
+    len: usize,
+    samples: &[T],
+    source: &mut Option<SourceRef>,
+) {
+    if let Some(channel) = tracks.channels.get_mut(track) {
+        channel
+            .samples
+            .lock()
+            .unwrap()
+            .splice(start..start + len, samples.iter().copied());
+        std::mem::swap(&mut channel.source, source);
+        tracks.spectrograms[track].refresh();
+    }
+}
+
+fn set_strip<T: Sample>(tracks: &mut Tracks<T>, track: usize, strip: Strip) {
+    if let Some(channel) = tracks.channels.get_mut(track) {
+        channel.strip = strip;
+    }
+}
+
+fn insert_effect<T: Sample>(
+    tracks: &mut Tracks<T>,
+    track: usize,
+    index: usize,
+    stash: &mut Option<Box<dyn Effect>>,
+) {
+    if let (Some(channel), Some(effect)) = (tracks.channels.get(track), stash.take()) {
+        channel.effects.lock().unwrap().insert(index, effect);
+    }
+}
+
+fn remove_effect<T: Sample>(
+    tracks: &mut Tracks<T>,
+    track: usize,
+    index: usize,
+) -> Option<Box<dyn Effect>> {
+    let channel = tracks.channels.get(track)?;
+    let effect = channel.effects.lock().unwrap().remove(index);
+    effect
+}
+
+fn move_effect<T: Sample>(tracks: &mut Tracks<T>, track: usize, from: usize, to: usize) {
+    if let Some(channel) = tracks.channels.get(track) {
+        let mut effects = channel.effects.lock().unwrap();
+        if let Some(effect) = effects.remove(from) {
+            effects.insert(to, effect);
+        }
+    }
+}
+
+fn set_parameter<T: Sample>(
+    tracks: &mut Tracks<T>,
+    track: usize,
+    effect: usize,
+    parameter: usize,
+    value: f32,
+) {
+    if let Some(channel) = tracks.channels.get(track) {
+        if let Some(effect) = channel.effects.lock().unwrap().get_mut(effect) {
+            effect.set_parameter(parameter, value);
+        }
+    }
+}
+
+fn set_settings<T: Sample>(tracks: &mut Tracks<T>, track: usize, settings: Settings) {
+    if let Some(spectrogram) = tracks.spectrograms.get_mut(track) {
+        *spectrogram = spectrogram.clone().with_settings(settings);
+    }
+}
+
+// Which settings differ, so repeated changes to the same control can be merged.
+fn changed_fields(a: &Settings, b: &Settings) -> [bool; 5] {
+    [
+        a.fft_size != b.fft_size,
+        a.hop_size != b.hop_size,
+        a.window != b.window,
+        a.db_floor != b.db_floor,
+        a.db_ceiling != b.db_ceiling,
+    ]
+}
+
+fn changed_strip_fields(a: &Strip, b: &Strip) -> [bool; 5] {
+    [
+        a.gain_db != b.gain_db,
+        a.pan != b.pan,
+        a.pan_law != b.pan_law,
+        a.mute != b.mute,
+        a.solo != b.solo,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::Delay;
+    use crate::history::History;
+    use std::path::PathBuf;
+
+    fn tracks(samples: Vec<f32>) -> Tracks<f32> {
+        let mut channel = Channel::new();
+        *channel.samples.lock().unwrap() = samples;
+        channel.source = Some(SourceRef {
+            path: PathBuf::from("source.wav"),
+            channel: 0,
+        });
+        let mut tracks = Tracks::default();
+        tracks.put(0, vec![channel.with_spectrogram()]);
+        tracks
+    }
+
+    fn samples(tracks: &Tracks<f32>) -> Vec<f32> {
+        tracks.channels[0].samples.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn splices_undo_and_redo() {
+        let mut tracks = tracks(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let mut history = History::default();
+
+        let edit = Edit::splice(&tracks, 0, 1..3, vec![9.0]);
+        assert_eq!(
+            edit.applied_selection(),
+            Some(Selection {
+                track: 0,
+                range: 1..2
+            })
+        );
+        history.execute(edit, &mut tracks);
+        assert_eq!(samples(&tracks), vec![1.0, 9.0, 4.0, 5.0]);
+        // Edited samples no longer match the file they came from
+        assert_eq!(tracks.channels[0].source, None);
+
+        let edit = Edit::insert_silence(&tracks, 0, 4, 2);
+        history.execute(edit, &mut tracks);
+        assert_eq!(samples(&tracks), vec![1.0, 9.0, 4.0, 5.0, 0.0, 0.0]);
+
+        assert!(history.undo(&mut tracks));
+        assert!(history.undo(&mut tracks));
+        assert_eq!(samples(&tracks), vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert!(tracks.channels[0].source.is_some());
+
+        assert!(history.redo(&mut tracks));
+        assert_eq!(samples(&tracks), vec![1.0, 9.0, 4.0, 5.0]);
+        assert_eq!(tracks.channels[0].source, None);
+    }
+
+    #[test]
+    fn splice_range_is_clamped_to_the_track() {
+        let mut tracks = tracks(vec![1.0, 2.0, 3.0]);
+        let selection = Selection {
+            track: 0,
+            range: 1..100,
+        };
+        let mut history = History::default();
+        history.execute(Edit::trim(&tracks, &selection), &mut tracks);
+        assert_eq!(samples(&tracks), vec![2.0, 3.0]);
+        history.execute(Edit::delete(&tracks, &selection), &mut tracks);
+        assert_eq!(samples(&tracks), vec![2.0]);
+
+        history.undo(&mut tracks);
+        history.undo(&mut tracks);
+        assert_eq!(samples(&tracks), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn effects_come_and_go_with_undo() {
+        let mut tracks = tracks(vec![]);
+        let mut history = History::default();
+        history.execute(
+            Edit::InsertEffect {
+                track: 0,
+                index: 0,
+                stash: Some(Box::new(Delay::default())),
+            },
+            &mut tracks,
+        );
+        assert_eq!(tracks.channels[0].effects.lock().unwrap().len(), 1);
+        history.undo(&mut tracks);
+        assert!(tracks.channels[0].effects.lock().unwrap().is_empty());
+        history.redo(&mut tracks);
+        assert_eq!(tracks.channels[0].effects.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn only_edits_sparing_the_recording_track_are_allowed() {
+        let tracks = tracks(vec![0.0; 4]);
+        assert!(!Edit::splice(&tracks, 0, 0..1, vec![]).allowed_while_recording(0));
+        assert!(Edit::splice(&tracks, 0, 0..1, vec![]).allowed_while_recording(1));
+        assert!(!Edit::<f32>::remove_tracks(0, 1).allowed_while_recording(0));
+        assert!(Edit::<f32>::remove_tracks(1, 1).allowed_while_recording(0));
+    }
+}
@@ -0,0 +1,138 @@
+use super::{Effect, SavedEffect};
+
+/// An ordered list of effects, each processing the output of the one before.
+#[derive(Default)]
+pub struct Chain {
+    effects: Vec<Box<dyn Effect>>,
+    // The rate the effects were last prepared for.
+    sample_rate: Option<u32>,
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&dyn Effect> {
+        self.effects.get(index).map(|effect| effect.as_ref())
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Box<dyn Effect>> {
+        self.effects.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Effect> {
+        self.effects.iter().map(|effect| effect.as_ref())
+    }
+
+    /// Inserts `effect` at `index`, or at the end if `index` is past it.
+    pub fn insert(&mut self, index: usize, mut effect: Box<dyn Effect>) {
+        if let Some(sample_rate) = self.sample_rate {
+            effect.prepare(sample_rate);
+        }
+        self.effects.insert(index.min(self.effects.len()), effect);
+    }
+
+    pub fn push(&mut self, effect: Box<dyn Effect>) {
+        self.insert(self.effects.len(), effect);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Box<dyn Effect>> {
+        if index < self.effects.len() {
+            Some(self.effects.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// The total latency of every effect, in samples.
+    pub fn latency(&self) -> usize {
+        self.effects.iter().map(|effect| effect.latency()).sum()
+    }
+
+    /// The total tail of every effect, in samples.
+    pub fn tail(&self) -> usize {
+        self.effects.iter().map(|effect| effect.tail()).sum()
+    }
+
+    /// Prepares every effect for `sample_rate`, unless they already are.
+    pub fn prepare(&mut self, sample_rate: u32) {
+        if self.sample_rate != Some(sample_rate) {
+            self.sample_rate = Some(sample_rate);
+            for effect in self.effects.iter_mut() {
+                effect.prepare(sample_rate);
+            }
+        }
+    }
+
+    /// Whether every effect is prepared for `sample_rate`.
+    pub fn is_prepared(&self, sample_rate: u32) -> bool {
+        self.sample_rate == Some(sample_rate)
+    }
+
+    pub fn process(&mut self, block: &mut [f32]) {
+        for effect in self.effects.iter_mut() {
+            effect.process(block);
+        }
+    }
+
+    /// How many channels the chain puts out: as many as its last effect widens to. Effects
+    /// anywhere else only process in mono.
+    pub fn channels(&self) -> usize {
+        self.effects
+            .last()
+            .map_or(1, |effect| effect.channels().max(1))
+    }
+
+    /// Processes a mono block into `out`, which holds `channels` interleaved samples for each
+    /// sample of `block`. Every effect but the last processes `block` in place on the way.
+    pub fn process_wide(&mut self, block: &mut [f32], out: &mut [f32]) {
+        match self.effects.split_last_mut() {
+            Some((last, effects)) => {
+                for effect in effects.iter_mut() {
+                    effect.process(block);
+                }
+                last.process_wide(block, out);
+            }
+            None => out.copy_from_slice(block),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for effect in self.effects.iter_mut() {
+            effect.reset();
+        }
+    }
+
+    pub fn save(&self) -> Vec<SavedEffect> {
+        self.effects
+            .iter()
+            .map(|effect| SavedEffect {
+                name: effect.name().to_string(),
+                state: effect.save(),
+            })
+            .collect()
+    }
+}
+
+impl Clone for Chain {
+    /// Copies every effect as it is, through `Effect::duplicate`.
+    fn clone(&self) -> Self {
+        Self {
+            effects: self
+                .effects
+                .iter()
+                .map(|effect| effect.duplicate())
+                .collect(),
+            sample_rate: self.sample_rate,
+        }
+    }
+}
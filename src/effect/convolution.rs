@@ -0,0 +1,257 @@
+use super::{Effect, Parameter};
+use crate::dsp::{self, Convolver};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+const PARAMETERS: [Parameter; 2] = [
+    Parameter {
+        name: "Mix",
+        unit: "",
+        min: 0.0,
+        max: 1.0,
+        default: 0.3,
+        step: 0.01,
+    },
+    Parameter {
+        name: "Wet gain",
+        unit: "dB",
+        min: -24.0,
+        max: 12.0,
+        default: 0.0,
+        step: 0.5,
+    },
+];
+
+const MIX: usize = 0;
+const WET_GAIN: usize = 1;
+
+// The block size the convolver runs at, which is also the effect's latency.
+const BLOCK_SIZE: usize = 512;
+
+/// Convolution reverb: the input convolved with an impulse response, mixed with the input.
+///
+/// The impulse response may have several channels, as one recorded in stereo. Last in a chain, the
+/// effect then widens its input to as many channels, each convolved with its own impulse response;
+/// anywhere else it stays mono, with the channels averaged.
+///
+/// The impulse response is scaled to unit energy, averaged over its channels, so a wet gain of
+/// 0 dB keeps noise at the level it went in whatever space was recorded.
+#[derive(Clone)]
+pub struct Convolution {
+    values: [f32; 2],
+    impulses: Vec<Vec<f32>>,
+    impulse_rate: u32,
+    sample_rate: u32,
+    // One for each channel of the impulse response.
+    convolvers: Vec<Convolver>,
+    // Holds the dry signal back by the convolvers' latency, so both halves of the mix line up.
+    dry: Vec<f32>,
+    dry_position: usize,
+    // The dry signal of the block being processed, once held back, and one channel of its wet
+    // signal at a time.
+    delayed: Vec<f32>,
+    wet: Vec<f32>,
+}
+
+// What gets saved in a project: the impulse response itself, since the tracks it came from may
+// change or go away. Projects saved before multichannel impulse responses have a single `impulse`.
+#[derive(Serialize, Deserialize)]
+struct State {
+    parameters: Vec<f32>,
+    sample_rate: u32,
+    impulses: Vec<Vec<f32>>,
+}
+
+impl Convolution {
+    pub const NAME: &'static str = "Convolution";
+
+    /// A reverb for a mono impulse response recorded at `sample_rate`.
+    pub fn new(impulse: Vec<f32>, sample_rate: u32) -> Self {
+        Self::with_channels(vec![impulse], sample_rate)
+    }
+
+    /// A reverb for an impulse response with one channel of samples for each of `impulses`, all
+    /// recorded at `sample_rate`.
+    pub fn with_channels(mut impulses: Vec<Vec<f32>>, sample_rate: u32) -> Self {
+        if impulses.is_empty() {
+            impulses.push(vec![]);
+        }
+        let mut convolution = Self {
+            values: [PARAMETERS[MIX].default, PARAMETERS[WET_GAIN].default],
+            impulses,
+            impulse_rate: sample_rate.max(1),
+            sample_rate: sample_rate.max(1),
+            convolvers: vec![],
+            dry: vec![],
+            dry_position: 0,
+            delayed: vec![],
+            wet: vec![],
+        };
+        convolution.rebuild();
+        convolution
+    }
+
+    /// The impulse response's length, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.impulse_len() as f32 / self.impulse_rate as f32
+    }
+
+    // The length of the longest channel of the impulse response, in samples at its own rate.
+    fn impulse_len(&self) -> usize {
+        self.impulses.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    // Prepares the impulse response for the current sample rate.
+    fn rebuild(&mut self) {
+        let mut impulses: Vec<Vec<f32>> = self
+            .impulses
+            .iter()
+            .map(|impulse| dsp::resample(impulse, self.impulse_rate, self.sample_rate))
+            .collect();
+        let energy = impulses.iter().flatten().map(|s| s * s).sum::<f32>() / impulses.len() as f32;
+        if energy > 0.0 {
+            let scale = energy.sqrt().recip();
+            for sample in impulses.iter_mut().flatten() {
+                *sample *= scale;
+            }
+        }
+        self.convolvers = impulses
+            .iter()
+            .map(|impulse| Convolver::new(impulse, BLOCK_SIZE))
+            .collect();
+        self.dry = vec![0.0; BLOCK_SIZE];
+        self.dry_position = 0;
+        // Room for blocks up to the latency, as primed after a jump, so processing them doesn't
+        // allocate on an audio thread
+        self.delayed = Vec::with_capacity(BLOCK_SIZE);
+        self.wet = Vec::with_capacity(BLOCK_SIZE);
+    }
+
+    // Holds back the dry signal of `block` into `delayed`, and returns the dry and wet gains.
+    fn delay_dry(&mut self, block: &[f32]) -> (f32, f32) {
+        self.delayed.clear();
+        for sample in block {
+            self.delayed
+                .push(std::mem::replace(&mut self.dry[self.dry_position], *sample));
+            self.dry_position = (self.dry_position + 1) % self.dry.len();
+        }
+        let mix = self.values[MIX];
+        (1.0 - mix, 10f32.powf(self.values[WET_GAIN] / 20.0) * mix)
+    }
+}
+
+impl Default for Convolution {
+    /// A reverb with no impulse response yet, which is silent when fully wet.
+    fn default() -> Self {
+        Self::new(vec![], 44100)
+    }
+}
+
+impl Effect for Convolution {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        &PARAMETERS
+    }
+
+    fn parameter(&self, index: usize) -> f32 {
+        self.values[index]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let parameter = PARAMETERS[index];
+        self.values[index] = value.clamp(parameter.min, parameter.max);
+    }
+
+    fn prepare(&mut self, sample_rate: u32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.rebuild();
+        } else {
+            self.reset();
+        }
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        let (dry_gain, wet_gain) = self.delay_dry(block);
+        let wet_gain = wet_gain / self.convolvers.len() as f32;
+        // Every channel convolves the input, so the mix builds up alongside it
+        for sample in self.delayed.iter_mut() {
+            *sample *= dry_gain;
+        }
+        for convolver in self.convolvers.iter_mut() {
+            self.wet.clear();
+            self.wet.extend_from_slice(block);
+            convolver.process(&mut self.wet);
+            for (sample, wet) in self.delayed.iter_mut().zip(self.wet.iter()) {
+                *sample += wet * wet_gain;
+            }
+        }
+        block.copy_from_slice(&self.delayed);
+    }
+
+    fn channels(&self) -> usize {
+        self.convolvers.len()
+    }
+
+    fn process_wide(&mut self, block: &mut [f32], out: &mut [f32]) {
+        let channels = self.convolvers.len();
+        let (dry_gain, wet_gain) = self.delay_dry(block);
+        for (channel, convolver) in self.convolvers.iter_mut().enumerate() {
+            self.wet.clear();
+            self.wet.extend_from_slice(block);
+            convolver.process(&mut self.wet);
+            for (frame, (dry, wet)) in out
+                .chunks_mut(channels)
+                .zip(self.delayed.iter().zip(self.wet.iter()))
+            {
+                frame[channel] = dry * dry_gain + wet * wet_gain;
+            }
+        }
+    }
+
+    fn latency(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn duplicate(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn tail(&self) -> usize {
+        (self.impulse_len() as u64 * self.sample_rate as u64 / self.impulse_rate as u64) as usize
+    }
+
+    fn reset(&mut self) {
+        for convolver in self.convolvers.iter_mut() {
+            convolver.reset();
+        }
+        for sample in self.dry.iter_mut() {
+            *sample = 0.0;
+        }
+        self.dry_position = 0;
+    }
+
+    fn save(&self) -> serde_json::Value {
+        serde_json::to_value(State {
+            parameters: self.values.to_vec(),
+            sample_rate: self.impulse_rate,
+            impulses: self.impulses.clone(),
+        })
+        .unwrap_or_default()
+    }
+
+    fn restore(&mut self, state: &serde_json::Value) -> Result<()> {
+        let state: State = serde_json::from_value(state.clone())?;
+        if state.parameters.len() != PARAMETERS.len() {
+            return Err(Error::EffectState(Self::NAME.to_string()));
+        }
+        *self = Self::with_channels(state.impulses, state.sample_rate);
+        for (i, value) in state.parameters.into_iter().enumerate() {
+            self.set_parameter(i, value);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use super::{Effect, Parameter};
+
+const PARAMETERS: [Parameter; 3] = [
+    Parameter {
+        name: "Time",
+        unit: "ms",
+        min: 1.0,
+        max: 2000.0,
+        default: 250.0,
+        step: 1.0,
+    },
+    Parameter {
+        name: "Feedback",
+        unit: "",
+        min: 0.0,
+        max: 0.95,
+        default: 0.3,
+        step: 0.01,
+    },
+    Parameter {
+        name: "Mix",
+        unit: "",
+        min: 0.0,
+        max: 1.0,
+        default: 0.3,
+        step: 0.01,
+    },
+];
+
+const TIME: usize = 0;
+const FEEDBACK: usize = 1;
+const MIX: usize = 2;
+
+/// An echo: the input mixed with copies of itself that repeat and die away.
+#[derive(Clone)]
+pub struct Delay {
+    values: [f32; 3],
+    sample_rate: u32,
+    // Long enough for the longest delay time.
+    line: Vec<f32>,
+    write: usize,
+}
+
+impl Delay {
+    pub const NAME: &'static str = "Delay";
+
+    fn delay_samples(&self) -> usize {
+        ((self.values[TIME] / 1000.0 * self.sample_rate as f32) as usize).clamp(1, self.line.len())
+    }
+}
+
+impl Default for Delay {
+    fn default() -> Self {
+        let mut delay = Self {
+            values: [
+                PARAMETERS[TIME].default,
+                PARAMETERS[FEEDBACK].default,
+                PARAMETERS[MIX].default,
+            ],
+            sample_rate: 0,
+            line: vec![],
+            write: 0,
+        };
+        delay.prepare(44100);
+        delay
+    }
+}
+
+impl Effect for Delay {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        &PARAMETERS
+    }
+
+    fn parameter(&self, index: usize) -> f32 {
+        self.values[index]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let parameter = PARAMETERS[index];
+        self.values[index] = value.clamp(parameter.min, parameter.max);
+    }
+
+    fn prepare(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        let len = (PARAMETERS[TIME].max / 1000.0 * sample_rate as f32) as usize + 1;
+        self.line = vec![0.0; len];
+        self.write = 0;
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        let delay = self.delay_samples();
+        let len = self.line.len();
+        let (feedback, mix) = (self.values[FEEDBACK], self.values[MIX]);
+        for sample in block.iter_mut() {
+            let delayed = self.line[(self.write + len - delay) % len];
+            self.line[self.write] = *sample + delayed * feedback;
+            self.write = (self.write + 1) % len;
+            *sample = *sample * (1.0 - mix) + delayed * mix;
+        }
+    }
+
+    // Echoes are heard until they have died away by 60 dB.
+    fn tail(&self) -> usize {
+        let feedback = self.values[FEEDBACK];
+        let repeats = if feedback > 0.0 {
+            1.0 + (0.001f32.ln() / feedback.ln()).ceil()
+        } else {
+            1.0
+        };
+        self.delay_samples() * repeats as usize
+    }
+
+    fn duplicate(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn reset(&mut self) {
+        for sample in self.line.iter_mut() {
+            *sample = 0.0;
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::{Effect, Parameter};
+use crate::dsp::Biquad;
+
+const PARAMETERS: [Parameter; 3] = [
+    Parameter {
+        name: "Mode",
+        unit: "",
+        min: 0.0,
+        max: 1.0,
+        default: 0.0,
+        step: 1.0,
+    },
+    Parameter {
+        name: "Cutoff",
+        unit: "Hz",
+        min: 20.0,
+        max: 20000.0,
+        default: 1000.0,
+        step: 1.0,
+    },
+    Parameter {
+        name: "Q",
+        unit: "",
+        min: 0.1,
+        max: 10.0,
+        default: std::f32::consts::FRAC_1_SQRT_2,
+        step: 0.01,
+    },
+];
+
+const MODE: usize = 0;
+const CUTOFF: usize = 1;
+const Q: usize = 2;
+
+/// A resonant low-pass (mode 0) or high-pass (mode 1) biquad filter.
+#[derive(Clone)]
+pub struct Filter {
+    values: [f32; 3],
+    sample_rate: u32,
+    biquad: Biquad,
+}
+
+impl Filter {
+    pub const NAME: &'static str = "Filter";
+
+    // Tunes the filter to the current parameters.
+    fn update(&mut self) {
+        let nyquist = self.sample_rate as f32 / 2.0;
+        let cutoff = self.values[CUTOFF].min(nyquist * 0.99) as f64;
+        let q = self.values[Q] as f64;
+        let tuned = if self.values[MODE] < 0.5 {
+            Biquad::low_pass(cutoff, q, self.sample_rate)
+        } else {
+            Biquad::high_pass(cutoff, q, self.sample_rate)
+        };
+        self.biquad.retune(&tuned);
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        let mut filter = Self {
+            values: [
+                PARAMETERS[MODE].default,
+                PARAMETERS[CUTOFF].default,
+                PARAMETERS[Q].default,
+            ],
+            sample_rate: 44100,
+            biquad: Biquad::new([1.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+        };
+        filter.update();
+        filter
+    }
+}
+
+impl Effect for Filter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        &PARAMETERS
+    }
+
+    fn parameter(&self, index: usize) -> f32 {
+        self.values[index]
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let parameter = PARAMETERS[index];
+        self.values[index] = value.clamp(parameter.min, parameter.max);
+        self.update();
+    }
+
+    fn prepare(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.update();
+        self.reset();
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.biquad.process(*sample as f64) as f32;
+        }
+    }
+
+    fn duplicate(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn reset(&mut self) {
+        self.biquad.reset();
+    }
+}
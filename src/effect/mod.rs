@@ -0,0 +1,163 @@
+//! Audio effects that can be chained onto tracks.
+//!
+//! An effect is anything implementing `Effect`. To make a new effect available in the editor,
+//! register it in `Registry::default`; the editor lists, saves and restores effects through the
+//! registry and never needs to know about them otherwise.
+
+pub mod chain;
+pub mod convolution;
+pub mod delay;
+pub mod filter;
+
+pub use chain::Chain;
+pub use convolution::Convolution;
+pub use delay::Delay;
+pub use filter::Filter;
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Describes one of an effect's parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    /// The smallest change that makes a difference, for sliders.
+    pub step: f32,
+}
+
+/// A mono audio processor, which may widen its output to several channels at the end of a chain.
+pub trait Effect: Send {
+    /// The name the effect is listed and saved under.
+    fn name(&self) -> &'static str;
+
+    fn parameters(&self) -> &[Parameter];
+
+    fn parameter(&self, index: usize) -> f32;
+
+    /// Sets parameter `index`, clamped to its range.
+    fn set_parameter(&mut self, index: usize, value: f32);
+
+    /// Called before the first block, and again whenever the sample rate changes.
+    fn prepare(&mut self, sample_rate: u32);
+
+    /// Processes a block of samples in place.
+    fn process(&mut self, block: &mut [f32]);
+
+    /// How many channels the effect puts out when it widens its input.
+    fn channels(&self) -> usize {
+        1
+    }
+
+    /// Processes a mono block into `out`, which holds `channels` interleaved samples for each
+    /// sample of `block`. This is used instead of `process` when the effect ends a chain, and
+    /// `block` may be left changed. By default the block is processed in place and copied to
+    /// every channel.
+    fn process_wide(&mut self, block: &mut [f32], out: &mut [f32]) {
+        self.process(block);
+        for (frame, sample) in out.chunks_mut(self.channels().max(1)).zip(block.iter()) {
+            for out in frame.iter_mut() {
+                *out = *sample;
+            }
+        }
+    }
+
+    /// How many samples the output lags behind the input.
+    fn latency(&self) -> usize {
+        0
+    }
+
+    /// How many samples the output goes on for once the input stops, as the echoes of a delay or
+    /// the decay of a reverb.
+    fn tail(&self) -> usize {
+        0
+    }
+
+    /// Forgets any audio held from earlier blocks, as after a seek.
+    fn reset(&mut self) {}
+
+    /// A copy of the effect as it is, so the copy can process elsewhere, as when rendering
+    /// offline, without disturbing the original.
+    fn duplicate(&self) -> Box<dyn Effect>;
+
+    /// The effect's state, for saving in a project. By default this is the parameter values.
+    fn save(&self) -> serde_json::Value {
+        (0..self.parameters().len())
+            .map(|i| self.parameter(i))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Restores state returned by `save`.
+    fn restore(&mut self, state: &serde_json::Value) -> Result<()> {
+        let values: Vec<f32> = serde_json::from_value(state.clone())?;
+        if values.len() != self.parameters().len() {
+            return Err(Error::EffectState(self.name().to_string()));
+        }
+        for (i, value) in values.into_iter().enumerate() {
+            self.set_parameter(i, value);
+        }
+        Ok(())
+    }
+}
+
+/// An effect as stored in a project file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedEffect {
+    pub name: String,
+    pub state: serde_json::Value,
+}
+
+/// Makes an effect with its default parameters.
+pub type Constructor = fn() -> Box<dyn Effect>;
+
+/// The effects that can be added to a track, by name.
+pub struct Registry {
+    effects: Vec<(&'static str, Constructor)>,
+}
+
+impl Registry {
+    /// A registry with no effects in it.
+    pub fn new() -> Self {
+        Self { effects: vec![] }
+    }
+
+    pub fn register(&mut self, name: &'static str, create: Constructor) {
+        self.effects.push((name, create));
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.effects.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// Creates the named effect with its default parameters.
+    pub fn create(&self, name: &str) -> Option<Box<dyn Effect>> {
+        self.effects
+            .iter()
+            .find(|(registered, _)| *registered == name)
+            .map(|(_, create)| create())
+    }
+
+    /// Recreates an effect saved in a project.
+    pub fn restore(&self, saved: &SavedEffect) -> Result<Box<dyn Effect>> {
+        let mut effect = self
+            .create(&saved.name)
+            .ok_or_else(|| Error::UnknownEffect(saved.name.clone()))?;
+        effect.restore(&saved.state)?;
+        Ok(effect)
+    }
+}
+
+impl Default for Registry {
+    /// The effects built into the editor.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Filter::NAME, || Box::new(Filter::default()));
+        registry.register(Delay::NAME, || Box::new(Delay::default()));
+        registry.register(Convolution::NAME, || Box::new(Convolution::default()));
+        registry
+    }
+}
@@ -0,0 +1,109 @@
+use crate::playback;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while editing, so it can be shown to the user instead of panicking.
+#[derive(Debug)]
+pub enum Error {
+    Dialog(native_dialog::Error),
+    MissingExtension(PathBuf),
+    UnsupportedFormat(String),
+    /// A file's audio is encoded with a codec there is no decoder for.
+    UnsupportedCodec(&'static str),
+    Wav(hound::Error),
+    Flac(claxon::Error),
+    Codec(symphonia::core::errors::Error),
+    Playback(playback::Error),
+    Io(std::io::Error),
+    Project(serde_json::Error),
+    /// A project file was written by a newer version of the editor.
+    ProjectVersion(u32),
+    /// A project uses an effect that isn't registered.
+    UnknownEffect(String),
+    /// Saved state didn't fit the named effect.
+    EffectState(String),
+    /// The receiving end of a track's sample channel was dropped.
+    Disconnected,
+    /// An edit would have moved the track being recorded into, or changed its samples.
+    Recording,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Dialog(e) => write!(f, "Couldn't open the file dialog: {}", e),
+            Error::MissingExtension(path) => {
+                write!(f, "{} has no file extension", path.display())
+            }
+            Error::UnsupportedFormat(extension) => {
+                write!(f, "\".{}\" files aren't supported", extension)
+            }
+            Error::UnsupportedCodec(codec) => write!(f, "Unsupported codec: {}", codec),
+            Error::Wav(e) => write!(f, "Couldn't decode WAV file: {}", e),
+            Error::Flac(e) => write!(f, "Couldn't decode FLAC file: {}", e),
+            Error::Codec(e) => write!(f, "Couldn't decode audio file: {}", e),
+            Error::Playback(e) => write!(f, "Playback failed: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Project(e) => write!(f, "Couldn't read project file: {}", e),
+            Error::ProjectVersion(version) => write!(
+                f,
+                "The project file is version {}, but only up to version {} is supported",
+                version,
+                crate::project::VERSION
+            ),
+            Error::UnknownEffect(name) => write!(f, "There is no effect called \"{}\"", name),
+            Error::EffectState(name) => write!(f, "Couldn't restore the {} effect", name),
+            Error::Disconnected => write!(f, "The track's sample channel was closed"),
+            Error::Recording => write!(
+                f,
+                "Stop recording before moving the recording track or editing its samples"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<native_dialog::Error> for Error {
+    fn from(e: native_dialog::Error) -> Self {
+        Error::Dialog(e)
+    }
+}
+
+impl From<hound::Error> for Error {
+    fn from(e: hound::Error) -> Self {
+        Error::Wav(e)
+    }
+}
+
+impl From<claxon::Error> for Error {
+    fn from(e: claxon::Error) -> Self {
+        Error::Flac(e)
+    }
+}
+
+impl From<symphonia::core::errors::Error> for Error {
+    fn from(e: symphonia::core::errors::Error) -> Self {
+        Error::Codec(e)
+    }
+}
+
+impl From<playback::Error> for Error {
+    fn from(e: playback::Error) -> Self {
+        Error::Playback(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Project(e)
+    }
+}
@@ -0,0 +1,115 @@
+use crate::measure::Loudness;
+use crate::playback::{Mixer, Source};
+use crate::Result;
+use dasp::sample::types::i24;
+use dasp::sample::I24;
+use dasp::Sample;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fmt;
+use std::path::Path;
+
+/// The sample format written to an exported file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitDepth {
+    Int16,
+    Int24,
+    Int32,
+    Float32,
+}
+
+impl BitDepth {
+    pub const ALL: [BitDepth; 4] = [
+        BitDepth::Int16,
+        BitDepth::Int24,
+        BitDepth::Int32,
+        BitDepth::Float32,
+    ];
+}
+
+impl fmt::Display for BitDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BitDepth::Int16 => "16-bit",
+            BitDepth::Int24 => "24-bit",
+            BitDepth::Int32 => "32-bit",
+            BitDepth::Float32 => "32-bit float",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub bit_depth: BitDepth,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl Settings {
+    pub const SAMPLE_RATES: [u32; 5] = [44100, 48000, 88200, 96000, 192000];
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bit_depth: BitDepth::Int24,
+            sample_rate: 48000,
+            channels: 2,
+        }
+    }
+}
+
+/// Renders `sources` through the same mixer playback uses and writes the result as a WAV file.
+pub fn export<T: Sample, P: AsRef<Path>>(
+    path: P,
+    sources: Vec<Source<T>>,
+    settings: Settings,
+) -> Result<()> {
+    let (samples, _) = render(sources, settings.sample_rate, settings.channels);
+    write_wav(path, &samples, settings)
+}
+
+/// Renders `sources` from start to end through the same mixer playback uses, as interleaved
+/// frames of `channels` channels at `sample_rate`, along with the loudness of the result.
+pub fn render<T: Sample>(
+    sources: Vec<Source<T>>,
+    sample_rate: u32,
+    channels: u16,
+) -> (Vec<f32>, Loudness) {
+    let mut mixer = Mixer::new(sample_rate, channels);
+    mixer.set_sources(sources);
+    let samples = mixer.render_all();
+    (samples, mixer.loudness().1)
+}
+
+/// Writes interleaved samples to a WAV file, clipping them to full scale for integer formats.
+pub fn write_wav<P: AsRef<Path>>(path: P, samples: &[f32], settings: Settings) -> Result<()> {
+    let (bits_per_sample, sample_format) = match settings.bit_depth {
+        BitDepth::Int16 => (16, SampleFormat::Int),
+        BitDepth::Int24 => (24, SampleFormat::Int),
+        BitDepth::Int32 => (32, SampleFormat::Int),
+        BitDepth::Float32 => (32, SampleFormat::Float),
+    };
+    let spec = WavSpec {
+        channels: settings.channels,
+        sample_rate: settings.sample_rate,
+        bits_per_sample,
+        sample_format,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for sample in samples {
+        let clipped = sample.clamp(-1.0, 1.0);
+        match settings.bit_depth {
+            BitDepth::Int16 => writer.write_sample(clipped.to_sample::<i16>())?,
+            // Full scale converts one step past `i24::MAX`
+            BitDepth::Int24 => {
+                writer.write_sample(clipped.to_sample::<I24>().inner().min(i24::MAX.inner()))?
+            }
+            BitDepth::Int32 => writer.write_sample(clipped.to_sample::<i32>())?,
+            BitDepth::Float32 => writer.write_sample(*sample)?,
+        }
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// A reversible change to some target state.
+pub trait Command<S> {
+    fn apply(&mut self, target: &mut S);
+    fn revert(&mut self, target: &mut S);
+    /// Folds `next`, which has just been applied, into this command so both are undone in one
+    /// step. Used for continuous changes such as dragging a slider. Only commands executed within
+    /// `History::MERGE_WINDOW` of each other are offered for merging.
+    fn merge(&mut self, _next: &Self) -> bool {
+        false
+    }
+}
+
+/// An undo/redo stack holding at most `depth` undoable commands.
+pub struct History<C> {
+    done: VecDeque<C>,
+    undone: Vec<C>,
+    depth: usize,
+    // When the last command was executed, while it may still take merges.
+    last_executed: Option<Instant>,
+}
+
+impl<C> History<C> {
+    pub const DEFAULT_DEPTH: usize = 100;
+
+    /// How soon a command has to follow the one before to be merged into it. A slider drag sends
+    /// changes far more often than this, while separate adjustments are made further apart.
+    pub const MERGE_WINDOW: Duration = Duration::from_millis(500);
+
+    pub fn new(depth: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: vec![],
+            depth,
+            last_executed: None,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Changes how many commands are kept, forgetting the oldest ones if there are too many.
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.done.len() > depth {
+            self.done.pop_front();
+        }
+    }
+
+    /// Applies `command` and records it, discarding anything that could be redone.
+    pub fn execute<S>(&mut self, command: C, target: &mut S)
+    where
+        C: Command<S>,
+    {
+        self.execute_at(command, target, Instant::now());
+    }
+
+    fn execute_at<S>(&mut self, mut command: C, target: &mut S, now: Instant)
+    where
+        C: Command<S>,
+    {
+        command.apply(target);
+        self.undone.clear();
+
+        let recent = self
+            .last_executed
+            .replace(now)
+            .is_some_and(|last| now.duration_since(last) <= Self::MERGE_WINDOW);
+        if let Some(last) = self.done.back_mut().filter(|_| recent) {
+            if last.merge(&command) {
+                return;
+            }
+        }
+
+        self.done.push_back(command);
+        if self.done.len() > self.depth {
+            self.done.pop_front();
+        }
+    }
+
+    /// Reverts the last command, returning whether there was one.
+    pub fn undo<S>(&mut self, target: &mut S) -> bool
+    where
+        C: Command<S>,
+    {
+        match self.done.pop_back() {
+            Some(mut command) => {
+                command.revert(target);
+                self.undone.push(command);
+                self.last_executed = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-applies the last undone command, returning whether there was one.
+    pub fn redo<S>(&mut self, target: &mut S) -> bool
+    where
+        C: Command<S>,
+    {
+        match self.undone.pop() {
+            Some(mut command) => {
+                command.apply(target);
+                self.done.push_back(command);
+                self.last_executed = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+        self.last_executed = None;
+    }
+}
+
+impl<C> Default for History<C> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Adds to a number, merging with other additions.
+    struct Add(i32);
+
+    impl Command<i32> for Add {
+        fn apply(&mut self, target: &mut i32) {
+            *target += self.0;
+        }
+
+        fn revert(&mut self, target: &mut i32) {
+            *target -= self.0;
+        }
+
+        fn merge(&mut self, next: &Self) -> bool {
+            self.0 += next.0;
+            true
+        }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        let mut value = 0;
+        let start = Instant::now();
+        history.execute_at(Add(1), &mut value, start);
+        history.execute_at(Add(2), &mut value, start + History::<Add>::MERGE_WINDOW * 2);
+        assert_eq!(value, 3);
+
+        assert!(history.undo(&mut value));
+        assert_eq!(value, 1);
+        assert!(history.undo(&mut value));
+        assert_eq!(value, 0);
+        assert!(!history.undo(&mut value));
+
+        assert!(history.redo(&mut value));
+        assert_eq!(value, 1);
+        history.execute_at(Add(5), &mut value, start);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn merges_only_within_the_window() {
+        let mut history = History::default();
+        let mut value = 0;
+        let start = Instant::now();
+        let step = History::<Add>::MERGE_WINDOW / 2;
+        // One drag, then another after a pause
+        history.execute_at(Add(1), &mut value, start);
+        history.execute_at(Add(1), &mut value, start + step);
+        history.execute_at(Add(1), &mut value, start + step * 2);
+        history.execute_at(Add(1), &mut value, start + step * 5);
+        assert_eq!(value, 4);
+
+        assert!(history.undo(&mut value));
+        assert_eq!(value, 3);
+        assert!(history.undo(&mut value));
+        assert_eq!(value, 0);
+    }
+
+    #[test]
+    fn undo_ends_a_merge() {
+        let mut history = History::default();
+        let mut value = 0;
+        let now = Instant::now();
+        history.execute_at(Add(1), &mut value, now);
+        history.execute_at(Add(1), &mut value, now);
+        history.undo(&mut value);
+        history.execute_at(Add(1), &mut value, now);
+        history.undo(&mut value);
+        assert_eq!(value, 0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn depth_forgets_the_oldest() {
+        let mut history = History::new(2);
+        let mut value = 0;
+        let start = Instant::now();
+        for i in 0..3 {
+            history.execute_at(
+                Add(1),
+                &mut value,
+                start + History::<Add>::MERGE_WINDOW * 2 * i,
+            );
+        }
+        assert!(history.undo(&mut value));
+        assert!(history.undo(&mut value));
+        assert!(!history.undo(&mut value));
+        assert_eq!(value, 1);
+
+        history.set_depth(0);
+        assert!(!history.can_undo());
+    }
+}
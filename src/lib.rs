@@ -1,2 +1,16 @@
+pub mod decode;
+pub mod dsp;
+pub mod edit;
+pub mod effect;
+pub mod error;
+pub mod export;
+pub mod history;
+pub mod measure;
+pub mod playback;
+pub mod project;
 pub mod style;
-pub mod widgets;
\ No newline at end of file
+pub mod time;
+pub mod track;
+pub mod widgets;
+
+pub use error::{Error, Result};
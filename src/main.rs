@@ -1,37 +1,69 @@
 use dasp::Sample;
 use iced::{
-    button, scrollable, Align, Button, Column, Container, Element, Length, Radio, Row, Rule,
-    Sandbox, Scrollable, Settings, Text,
+    button, executor, pick_list, scrollable, slider, Align, Application, Button, Checkbox,
+    Clipboard, Color, Column, Command, Container, Element, Length, PickList, Radio, Row, Rule,
+    Scrollable, Settings, Slider, Space, Subscription, Text,
 };
+use iced_native::{event, keyboard, Event};
+use impulse_editor::decode::{self, AudioInfo};
+use impulse_editor::dsp::{self, impulse, Preparation, Window};
+use impulse_editor::edit::Edit;
+use impulse_editor::effect::{Chain, Convolution, Registry};
+use impulse_editor::export::{self, BitDepth};
+use impulse_editor::history::History;
+use impulse_editor::measure::{self, acoustics, Acoustics, Loudness, Sweep};
+use impulse_editor::playback::{self, Engine, PanLaw, Recorder, Source, Strip};
+use impulse_editor::project::{self, Project, SourceRef};
 use impulse_editor::style;
-use impulse_editor::widgets::spectrogram::BufferSize;
-use impulse_editor::widgets::Spectrogram;
+use impulse_editor::track::{Buffer, Channel, Clip, Selection, Tracks};
+use impulse_editor::widgets::spectrogram::{self, BufferSize, Navigate, Viewport};
+use impulse_editor::widgets::{timeline, Decay, Spectrogram, Timeline};
+use impulse_editor::{time, Error};
 use native_dialog::FileDialog;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-struct Channel<T> {
-    samples: Arc<Mutex<Vec<T>>>,
-    channel: (Sender<Arc<Mutex<Vec<T>>>>, Receiver<Arc<Mutex<Vec<T>>>>),
+// The width of the mixer strip beside each track, in pixels.
+const STRIP_WIDTH: u16 = 150;
+
+// The widget state of one effect in the selected track's chain.
+#[derive(Default)]
+struct EffectState {
+    up_button: button::State,
+    remove_button: button::State,
+    parameter_sliders: Vec<slider::State>,
 }
 
-impl<T> Channel<T>
-where
-    T: Sample,
-    T: Default,
-{
-    fn new() -> Self {
-        Self {
-            channel: mpsc::channel(),
-            samples: Arc::new(Mutex::new(vec![])),
-        }
-    }
-    fn assign_sender(&self) -> Sender<Arc<Mutex<Vec<T>>>> {
-        self.channel.0.clone()
+// The widget state of one track's mixer strip.
+#[derive(Default)]
+struct StripState {
+    gain_slider: slider::State,
+    pan_slider: slider::State,
+}
+
+// A track offered in a pick list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrackChoice {
+    index: usize,
+    name: String,
+}
+
+impl std::fmt::Display for TrackChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.index + 1, self.name)
     }
 }
 
+// A preparation step tried out on a track, heard and drawn in place of the track until it is
+// applied or discarded.
+struct Preview<T> {
+    track: usize,
+    preparation: Preparation,
+    samples: Buffer<T>,
+}
+
 // The App's state, which contains values that the program uses.
 #[derive(Default)]
 struct State<T> {
@@ -43,8 +75,1025 @@ struct State<T> {
     spectrogram_display_scroll: scrollable::State,
     add_new_channel_button: button::State,
     import_audio_button: button::State,
-    spectrograms: Vec<Spectrogram<T>>,
-    channels: Vec<Channel<T>>,
+    open_project_button: button::State,
+    save_project_button: button::State,
+    remove_track_button: button::State,
+    tracks: Tracks<T>,
+    history: History<Edit<T>>,
+    history_depth_slider: slider::State,
+    selection: Option<Selection>,
+    viewport: Viewport,
+    // The play position, in seconds.
+    playhead: f64,
+    timeline: timeline::State,
+    reset_zoom_button: button::State,
+    clipboard: Option<Clip<T>>,
+    // The length of silence to insert, in seconds.
+    silence_length: f32,
+    silence_length_slider: slider::State,
+    cut_button: button::State,
+    copy_button: button::State,
+    paste_button: button::State,
+    delete_button: button::State,
+    trim_button: button::State,
+    insert_silence_button: button::State,
+    engine: Option<Engine<T>>,
+    recorder: Option<Recorder<T>>,
+    // The track being recorded into, and its length before recording started.
+    recording: Option<(usize, usize)>,
+    // The track to record into. A new track is made when none is armed.
+    armed: Option<usize>,
+    record_button: button::State,
+    strip_states: Vec<StripState>,
+    pan_law_pick_list: pick_list::State<PanLaw>,
+    registry: Registry,
+    effect_states: Vec<EffectState>,
+    add_effect_pick_list: pick_list::State<&'static str>,
+    // The track to use as an impulse response.
+    impulse_track: Option<usize>,
+    impulse_pick_list: pick_list::State<TrackChoice>,
+    convolve_button: button::State,
+    add_reverb_button: button::State,
+    sweep: Sweep,
+    sweep_duration_slider: slider::State,
+    sweep_gain_slider: slider::State,
+    generate_sweep_button: button::State,
+    deconvolve_button: button::State,
+    loopback_button: button::State,
+    preview: Option<Preview<T>>,
+    // The length of a tail fade, in seconds.
+    fade_length: f32,
+    fade_length_slider: slider::State,
+    // The peak level to normalize to, in dBFS.
+    normalize_level: f32,
+    normalize_level_slider: slider::State,
+    find_onset_button: button::State,
+    trim_pre_delay_button: button::State,
+    fade_tail_button: button::State,
+    normalize_button: button::State,
+    minimum_phase_button: button::State,
+    apply_preview_button: button::State,
+    discard_preview_button: button::State,
+    // The loudness of each track and of the output, as last read from the engine.
+    meters: Option<(Vec<Loudness>, Loudness)>,
+    // The last whole-file loudness measurement, and what was measured.
+    loudness: Option<(String, Loudness)>,
+    analyse_track_loudness_button: button::State,
+    analyse_mix_loudness_button: button::State,
+    // The last room acoustics analysis, and the name of the track it was made from.
+    acoustics: Option<(String, Acoustics)>,
+    analyse_button: button::State,
+    export_acoustics_button: button::State,
+    error: Option<Error>,
+    dismiss_error_button: button::State,
+    selected_track: usize,
+    fft_size_pick_list: pick_list::State<usize>,
+    hop_size_pick_list: pick_list::State<usize>,
+    window_pick_list: pick_list::State<Window>,
+    db_floor_slider: slider::State,
+    db_ceiling_slider: slider::State,
+    export_settings: export::Settings,
+    bit_depth_pick_list: pick_list::State<BitDepth>,
+    sample_rate_pick_list: pick_list::State<u32>,
+    export_track_button: button::State,
+    export_mix_button: button::State,
+}
+
+impl<T> State<T>
+where
+    T: Sample + Default + Send + 'static,
+{
+    // Hands every channel to the playback engine, opening the output device on first use.
+    fn play(&mut self) -> impulse_editor::Result<()> {
+        let sources = self.sources();
+        let engine = match &mut self.engine {
+            Some(engine) => engine,
+            None => self.engine.insert(Engine::new()?),
+        };
+
+        engine.set_sources(sources);
+        engine.seek((self.playhead * engine.sample_rate() as f64) as u64);
+        Ok(engine.play()?)
+    }
+
+    // Starts capturing from the default input into the armed track, or into a new one.
+    fn record(&mut self) -> impulse_editor::Result<()> {
+        let recorder = match &mut self.recorder {
+            Some(recorder) => recorder,
+            None => self.recorder.insert(Recorder::new()?),
+        };
+        let sample_rate = recorder.sample_rate();
+
+        let track = match self.armed {
+            Some(track) => track,
+            None => {
+                let mut channel = Channel::<T>::new();
+                channel.name = format!("Recording {}", self.tracks.len() + 1);
+                channel.info = AudioInfo {
+                    sample_rate,
+                    channels: 1,
+                    ..AudioInfo::default()
+                };
+                let track = self.tracks.len();
+                self.edit(Edit::insert_tracks(track, vec![channel.with_spectrogram()]));
+                track
+            }
+        };
+
+        let samples = self.tracks.channels[track].samples.clone();
+        let start = samples.lock().unwrap().len();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(samples)?;
+        }
+        self.recording = Some((track, start));
+        self.selected_track = track;
+        Ok(())
+    }
+
+    // Stops capturing, turning what was recorded into an edit that can be undone.
+    fn stop_recording(&mut self) -> impulse_editor::Result<()> {
+        let (track, start) = match self.recording.take() {
+            Some(recording) => recording,
+            None => return Ok(()),
+        };
+        // What was captured is kept even if the stream couldn't be paused
+        let (stopped, input_rate) = match &mut self.recorder {
+            Some(recorder) => (recorder.stop(), recorder.sample_rate()),
+            None => return Ok(()),
+        };
+
+        let channel = &self.tracks.channels[track];
+        let recorded = channel.samples.lock().unwrap().split_off(start);
+        let recorded = dsp::resample(&recorded, input_rate, channel.info.sample_rate);
+        self.edit_samples(Edit::splice(&self.tracks, track, start..start, recorded));
+        stopped.map_err(Error::Playback)
+    }
+
+    // Shows what went wrong on either audio stream. A device that went away is let go of, along
+    // with what was being done on it, so the next attempt opens whichever device is the default.
+    fn check_streams(&mut self) {
+        if let Some(e) = self.engine.as_ref().and_then(Engine::error) {
+            if let playback::Error::Disconnected = e {
+                self.engine = None;
+                self.audio_playing = false;
+            }
+            self.error = Some(e.into());
+        }
+        if let Some(e) = self.recorder.as_ref().and_then(Recorder::error) {
+            if let playback::Error::Disconnected = e {
+                // Pausing the stream fails now, which the disconnection already explains
+                let _ = self.stop_recording();
+                self.recorder = None;
+            }
+            self.error = Some(e.into());
+        }
+    }
+
+    // Pauses playback, leaving the playhead where it stopped.
+    fn pause(&mut self) -> impulse_editor::Result<()> {
+        match &mut self.engine {
+            Some(engine) => {
+                engine.pause()?;
+                self.playhead = engine.position() as f64 / engine.sample_rate() as f64;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Asks for an audio file and adds each of its channels as a new track.
+    fn import_audio(&mut self) -> impulse_editor::Result<()> {
+        let file = FileDialog::new()
+            .set_location("~")
+            .add_filter("FLAC Audio File", &["flac"])
+            .add_filter("MPEG-3 Audio File", &["mp3"])
+            .add_filter("Ogg-Vorbis Audio File", &["ogg"])
+            .add_filter("WAV Audio File", &["wav"])
+            .show_open_single_file()?;
+
+        let file_out = match file {
+            Some(file_out) => file_out,
+            None => return Ok(()),
+        };
+
+        let decoded = decode::open::<T, _>(&file_out)?;
+
+        let name = match decoded.tags.get("TITLE") {
+            Some(title) => title.to_string(),
+            None => file_out
+                .file_stem()
+                .map_or(String::new(), |s| s.to_string_lossy().into_owned()),
+        };
+
+        // Every channel of the file becomes a mono track of its own
+        let buffers = decoded.deinterleave();
+        let channel_count = buffers.len();
+        let mut tracks = vec![];
+        for (i, samples) in buffers.into_iter().enumerate() {
+            let mut channel_out = Channel::<T>::new();
+            channel_out.name = if channel_count > 1 {
+                format!("{} ({})", name, decode::channel_label(i, channel_count))
+            } else {
+                name.clone()
+            };
+            channel_out.info = AudioInfo {
+                channels: 1,
+                ..decoded.info
+            };
+            channel_out.tags = decoded.tags.clone();
+            channel_out.source = Some(SourceRef {
+                path: file_out.clone(),
+                channel: i,
+            });
+            // The sides of a stereo file start out panned to their sides
+            if channel_count == 2 {
+                channel_out.strip.pan = if i == 0 { -1.0 } else { 1.0 };
+            }
+            *channel_out.samples.lock().unwrap() = samples;
+            tracks.push(channel_out.with_spectrogram());
+        }
+        self.edit(Edit::insert_tracks(self.tracks.len(), tracks));
+        self.selected_track = self.tracks.len() - 1;
+
+        Ok(())
+    }
+
+    fn save_project(&self) -> impulse_editor::Result<()> {
+        let file = FileDialog::new()
+            .set_location("~")
+            .add_filter("Impulse Project", &["impulse"])
+            .show_save_single_file()?;
+
+        let path = match file {
+            Some(path) => path.with_extension("impulse"),
+            None => return Ok(()),
+        };
+        self.write_project(&path)
+    }
+
+    fn write_project(&self, path: &Path) -> impulse_editor::Result<()> {
+        let mut project = Project::new(self.theme);
+        for (channel, spectrogram) in self
+            .tracks
+            .channels
+            .iter()
+            .zip(self.tracks.spectrograms.iter())
+        {
+            project.push_track(
+                path,
+                project::Track {
+                    name: channel.name.clone(),
+                    info: channel.info,
+                    tags: channel.tags.clone(),
+                    source: channel.source.clone(),
+                    audio: None,
+                    spectrogram: spectrogram.settings(),
+                    strip: channel.strip,
+                    effects: channel.effects.lock().unwrap().save(),
+                },
+                &channel.samples.lock().unwrap(),
+            )?;
+        }
+        project.write(path)
+    }
+
+    // Replaces every track with the ones saved in a project file.
+    fn open_project(&mut self) -> impulse_editor::Result<()> {
+        if self.recording.is_some() {
+            return Err(Error::Recording);
+        }
+        let file = FileDialog::new()
+            .set_location("~")
+            .add_filter("Impulse Project", &["impulse"])
+            .show_open_single_file()?;
+
+        let path = match file {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        self.load_project(&path)
+    }
+
+    fn load_project(&mut self, path: &Path) -> impulse_editor::Result<()> {
+        let project = Project::read(path)?;
+        let mut tracks = Tracks::default();
+        for track in project.tracks.iter() {
+            let mut channel = Channel::<T>::new();
+            channel.name = track.name.clone();
+            channel.info = track.info;
+            channel.tags = track.tags.clone();
+            channel.source = track.resolved_source(path);
+            channel.strip = track.strip;
+            for saved in track.effects.iter() {
+                channel
+                    .effects
+                    .lock()
+                    .unwrap()
+                    .push(self.registry.restore(saved)?);
+            }
+            *channel.samples.lock().unwrap() = track.load_samples(path)?;
+
+            let mut spectrogram =
+                Spectrogram::<T>::new(channel.assign_sender()).with_settings(track.spectrogram);
+            spectrogram.load(channel.samples.clone(), BufferSize::All);
+
+            tracks.channels.push(channel);
+            tracks.spectrograms.push(spectrogram);
+        }
+
+        self.pause()?;
+        self.audio_playing = false;
+        self.theme = project.theme;
+        self.tracks = tracks;
+        self.history.clear();
+        self.selected_track = 0;
+        self.selection = None;
+        self.playhead = 0.0;
+        // Indexes into the old tracks mean nothing in the new ones
+        self.armed = None;
+        self.impulse_track = None;
+        self.preview = None;
+        self.tracks_changed();
+        Ok(())
+    }
+
+    // Renders the given tracks to a WAV file picked by the user.
+    fn export(&self, sources: Vec<Source<T>>) -> impulse_editor::Result<()> {
+        let file = FileDialog::new()
+            .set_location("~")
+            .add_filter("WAV Audio File", &["wav"])
+            .show_save_single_file()?;
+
+        if let Some(file_out) = file {
+            export::export(
+                file_out.with_extension("wav"),
+                sources,
+                self.export_settings,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Changes the selected track's spectrogram settings through its builder API.
+    fn update_selected_spectrogram(&mut self, f: impl FnOnce(Spectrogram<T>) -> Spectrogram<T>) {
+        if let Some(spectrogram) = self.tracks.spectrograms.get(self.selected_track) {
+            let before = spectrogram.settings();
+            let after = f(spectrogram.clone()).settings();
+            if before != after {
+                self.edit(Edit::Spectrogram {
+                    track: self.selected_track,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    // Applies an edit to the tracks and records it for undo. While recording, edits that would
+    // move the recording track or change its samples are refused with an error.
+    fn edit(&mut self, edit: Edit<T>) {
+        match self.recording {
+            Some((track, _)) if !edit.allowed_while_recording(track) => {
+                self.error = Some(Error::Recording);
+            }
+            _ => {
+                self.history.execute(edit, &mut self.tracks);
+                self.tracks_changed();
+            }
+        }
+    }
+
+    // Undo and redo are refused while recording, since they could undo anything.
+    fn undo(&mut self) {
+        if self.recording.is_some() {
+            self.error = Some(Error::Recording);
+        } else if self.history.undo(&mut self.tracks) {
+            self.tracks_changed();
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.recording.is_some() {
+            self.error = Some(Error::Recording);
+        } else if self.history.redo(&mut self.tracks) {
+            self.tracks_changed();
+        }
+    }
+
+    // Adds a track holding the selected track convolved with the impulse response track. The
+    // result takes the impulse response's pan, so convolving with both sides of a stereo impulse
+    // response gives a stereo pair.
+    fn convolve(&mut self) {
+        let (dry, impulse) = match (
+            self.tracks.channels.get(self.selected_track),
+            self.impulse_track
+                .and_then(|track| self.tracks.channels.get(track)),
+        ) {
+            (Some(dry), Some(impulse)) => (dry, impulse),
+            _ => return,
+        };
+
+        let rate = dry.info.sample_rate;
+        let ir = dsp::resample(&impulse.float_samples(), impulse.info.sample_rate, rate);
+        let mut wet = dsp::convolve(&dry.float_samples(), &ir);
+        // Scaled down if it would clip
+        let peak = wet.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        if peak > 1.0 {
+            for sample in wet.iter_mut() {
+                *sample /= peak;
+            }
+        }
+
+        let mut channel = Channel::<T>::new();
+        channel.name = format!("{} * {}", dry.name, impulse.name);
+        channel.strip.pan = impulse.strip.pan;
+        self.add_track(channel, rate, wet);
+    }
+
+    // Adds a track of processed samples after the others and selects it.
+    fn add_track(&mut self, mut channel: Channel<T>, sample_rate: u32, samples: Vec<f32>) {
+        channel.info = AudioInfo {
+            sample_rate,
+            channels: 1,
+            ..AudioInfo::default()
+        };
+        *channel.samples.lock().unwrap() = from_floats(samples);
+
+        let index = self.tracks.len();
+        self.edit(Edit::insert_tracks(index, vec![channel.with_spectrogram()]));
+        self.selected_track = index;
+    }
+
+    // Adds a track holding a sine sweep with the current settings.
+    fn generate_sweep(&mut self) {
+        let mut channel = Channel::<T>::new();
+        channel.name = format!("Sweep ({} s)", self.sweep.duration);
+        self.add_track(channel, self.sweep.sample_rate, self.sweep.generate());
+    }
+
+    // Adds the impulse response deconvolved from the selected track, taken as the recording of a
+    // sweep with the current settings.
+    fn deconvolve(&mut self) {
+        let response = match self.tracks.channels.get(self.selected_track) {
+            Some(response) => response,
+            None => return,
+        };
+        let sweep = Sweep {
+            sample_rate: response.info.sample_rate,
+            ..self.sweep
+        };
+        let impulse = sweep.deconvolve(&response.float_samples());
+
+        let mut channel = Channel::<T>::new();
+        channel.name = format!("{} IR", response.name);
+        self.add_track(channel, sweep.sample_rate, impulse);
+    }
+
+    // Measures offline: the sweep goes through the impulse response track, if one is picked, and
+    // through a WAV file picked by the user, and comes back as a new response track.
+    fn loopback(&mut self) -> impulse_editor::Result<()> {
+        let file = FileDialog::new()
+            .set_location("~")
+            .add_filter("WAV Audio File", &["wav"])
+            .show_save_single_file()?;
+        let path = match file {
+            Some(path) => path.with_extension("wav"),
+            None => return Ok(()),
+        };
+
+        let system = self
+            .impulse_track
+            .and_then(|track| self.tracks.channels.get(track))
+            .map(|impulse| {
+                dsp::resample(
+                    &impulse.float_samples(),
+                    impulse.info.sample_rate,
+                    self.sweep.sample_rate,
+                )
+            });
+        let response = measure::loopback(&path, &self.sweep, system.as_deref())?;
+
+        let mut channel = Channel::<T>::new();
+        channel.name = String::from("Loopback response");
+        channel.source = Some(SourceRef { path, channel: 0 });
+        self.add_track(channel, self.sweep.sample_rate, response);
+        Ok(())
+    }
+
+    // The tracks making up the impulse response: every channel imported with the impulse
+    // response track, so a stereo impulse response is used whole, in file order.
+    fn impulse_channels(&self) -> Vec<&Channel<T>> {
+        let impulse = match self
+            .impulse_track
+            .and_then(|track| self.tracks.channels.get(track))
+        {
+            Some(impulse) => impulse,
+            None => return vec![],
+        };
+        let path = match &impulse.source {
+            Some(source) => &source.path,
+            None => return vec![impulse],
+        };
+        let mut channels: Vec<&Channel<T>> = self
+            .tracks
+            .channels
+            .iter()
+            .filter(|channel| {
+                channel.info.sample_rate == impulse.info.sample_rate
+                    && channel
+                        .source
+                        .as_ref()
+                        .is_some_and(|source| &source.path == path)
+            })
+            .collect();
+        channels.sort_by_key(|channel| channel.source.as_ref().map(|source| source.channel));
+        channels
+    }
+
+    // Adds a convolution reverb with the impulse response to the selected track's effects. A
+    // stereo impulse response makes the track stereo.
+    fn add_reverb(&mut self) {
+        let impulses = self.impulse_channels();
+        let (channel, sample_rate) = match (
+            self.tracks.channels.get(self.selected_track),
+            impulses.first(),
+        ) {
+            (Some(channel), Some(impulse)) => (channel, impulse.info.sample_rate),
+            _ => return,
+        };
+
+        let effect = Convolution::with_channels(
+            impulses
+                .iter()
+                .map(|impulse| impulse.float_samples())
+                .collect(),
+            sample_rate,
+        );
+        let index = channel.effects.lock().unwrap().len();
+        self.edit(Edit::InsertEffect {
+            track: self.selected_track,
+            index,
+            stash: Some(Box::new(effect)),
+        });
+    }
+
+    // The loudness of `sources` mixed as they would be played, after their gain and effects.
+    fn analyse_loudness(&self, sources: Vec<Source<T>>) -> Loudness {
+        let (sample_rate, channels) = match &self.engine {
+            Some(engine) => (engine.sample_rate(), engine.channels()),
+            None => (
+                self.export_settings.sample_rate,
+                self.export_settings.channels,
+            ),
+        };
+        export::render(sources, sample_rate, channels).1
+    }
+
+    // Writes the room acoustics analysis to a JSON file picked by the user.
+    fn export_acoustics(&self) -> impulse_editor::Result<()> {
+        let acoustics = match &self.acoustics {
+            Some((_, acoustics)) => acoustics,
+            None => return Ok(()),
+        };
+        let file = FileDialog::new()
+            .set_location("~")
+            .add_filter("JSON File", &["json"])
+            .show_save_single_file()?;
+
+        if let Some(path) = file {
+            acoustics.write(path.with_extension("json"))?;
+        }
+        Ok(())
+    }
+
+    // Selects the selected track's pre-delay and puts the playhead on the direct sound.
+    fn find_onset(&mut self) {
+        if let Some(channel) = self.tracks.channels.get(self.selected_track) {
+            if let Some(onset) = impulse::onset(&channel.float_samples()) {
+                self.selection = Some(Selection {
+                    track: self.selected_track,
+                    range: 0..onset,
+                });
+                self.playhead = onset as f64 / channel.info.sample_rate as f64;
+                if let Some(engine) = &self.engine {
+                    engine.seek((self.playhead * engine.sample_rate() as f64) as u64);
+                }
+            }
+        }
+    }
+
+    // Tries out a preparation step on the selected track.
+    fn preview(&mut self, preparation: Preparation) {
+        if let Some(channel) = self.tracks.channels.get(self.selected_track) {
+            let prepared = preparation.apply(&channel.float_samples(), channel.info.sample_rate);
+            self.preview = Some(Preview {
+                track: self.selected_track,
+                preparation,
+                samples: Arc::new(Mutex::new(from_floats(prepared))),
+            });
+            if let Some(engine) = &self.engine {
+                engine.set_sources(self.sources());
+            }
+        }
+    }
+
+    // Replaces the previewed track's samples with the preview's, as an edit that can be undone.
+    fn apply_preview(&mut self) {
+        let previewed = self.preview.as_ref().map(|preview| preview.track);
+        if previewed.is_some() && previewed == self.recording.map(|(track, _)| track) {
+            self.error = Some(Error::Recording);
+            return;
+        }
+        if let Some(preview) = self.preview.take() {
+            // Copied, since playback and the preview's spectrogram may still be reading them
+            let samples = preview.samples.lock().unwrap().clone();
+            self.edit_samples(Edit::splice(
+                &self.tracks,
+                preview.track,
+                0..usize::MAX,
+                samples,
+            ));
+            self.selection = None;
+        }
+    }
+
+    fn discard_preview(&mut self) {
+        if self.preview.take().is_some() {
+            if let Some(engine) = &self.engine {
+                engine.set_sources(self.sources());
+            }
+        }
+    }
+
+    // Changes the mixer settings of a track.
+    fn update_strip(&mut self, track: usize, f: impl FnOnce(&mut Strip)) {
+        if let Some(channel) = self.tracks.channels.get(track) {
+            let before = channel.strip;
+            let mut after = before;
+            f(&mut after);
+            if before != after {
+                self.edit(Edit::Strip {
+                    track,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    // Applies an edit to the samples of a track and selects whatever it inserted.
+    fn edit_samples(&mut self, edit: Edit<T>) {
+        let selection = edit.applied_selection();
+        self.edit(edit);
+        self.selection = selection;
+    }
+
+    // Where pastes and silence go: the selection, or the end of the selected track.
+    fn cursor(&self) -> Option<Selection> {
+        match &self.selection {
+            Some(selection) => Some(selection.clone()),
+            None => self
+                .tracks
+                .channels
+                .get(self.selected_track)
+                .map(|channel| {
+                    let len = channel.samples.lock().unwrap().len();
+                    Selection {
+                        track: self.selected_track,
+                        range: len..len,
+                    }
+                }),
+        }
+    }
+
+    // The selection, if it covers any samples.
+    fn selected_samples(&self) -> Option<Selection> {
+        self.selection.clone().filter(|s| !s.is_empty())
+    }
+
+    // The length of the longest track, in seconds.
+    fn duration(&self) -> f64 {
+        self.tracks
+            .channels
+            .iter()
+            .map(|channel| {
+                channel.samples.lock().unwrap().len() as f64 / channel.info.sample_rate as f64
+            })
+            .fold(0.0, f64::max)
+    }
+
+    // Keeps the selection and the playing sources in step with the tracks.
+    fn tracks_changed(&mut self) {
+        self.selected_track = self.selected_track.min(self.tracks.len().saturating_sub(1));
+        self.armed = self.armed.filter(|track| *track < self.tracks.len());
+        self.impulse_track = self
+            .impulse_track
+            .filter(|track| *track < self.tracks.len());
+        let tracks = &self.tracks;
+        self.selection = self.selection.take().and_then(|mut selection| {
+            let len = tracks
+                .channels
+                .get(selection.track)?
+                .samples
+                .lock()
+                .unwrap()
+                .len();
+            selection.range = selection.range.start.min(len)..selection.range.end.min(len);
+            Some(selection)
+        });
+        // A preview was made from samples that may have just changed
+        self.preview = None;
+        if let Some(engine) = &self.engine {
+            engine.set_sources(self.sources());
+        }
+    }
+
+    // What playback mixes: every track, with a preview heard in place of its track.
+    fn sources(&self) -> Vec<Source<T>> {
+        self.tracks
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(i, channel)| {
+                let mut source = channel.mixer_source();
+                if let Some(preview) = self.preview.as_ref().filter(|p| p.track == i) {
+                    source.samples = preview.samples.clone();
+                }
+                source
+            })
+            .collect()
+    }
+}
+
+// Converts processed samples back to the project's sample type.
+fn from_floats<T: Sample>(samples: Vec<f32>) -> Vec<T> {
+    samples
+        .into_iter()
+        .map(|s| T::Float::from_sample(s).to_sample::<T>())
+        .collect()
+}
+
+// Maps the editing shortcuts to messages, unless a widget already handled the key.
+fn shortcut(event: Event, status: event::Status) -> Option<Message> {
+    let (key_code, modifiers) = match (event, status) {
+        (
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }),
+            event::Status::Ignored,
+        ) => (key_code, modifiers),
+        _ => return None,
+    };
+
+    match key_code {
+        keyboard::KeyCode::Z if modifiers.is_command_pressed() => Some(if modifiers.shift {
+            Message::Redo
+        } else {
+            Message::Undo
+        }),
+        keyboard::KeyCode::X if modifiers.is_command_pressed() => Some(Message::Cut),
+        keyboard::KeyCode::C if modifiers.is_command_pressed() => Some(Message::Copy),
+        keyboard::KeyCode::V if modifiers.is_command_pressed() => Some(Message::Paste),
+        keyboard::KeyCode::Delete | keyboard::KeyCode::Backspace => Some(Message::Delete),
+        _ => None,
+    }
+}
+
+// A track's compact mixer strip: gain, pan, mute and solo.
+fn mixer_strip<'a>(
+    track: usize,
+    strip: &Strip,
+    loudness: Option<&Loudness>,
+    state: &'a mut StripState,
+    theme: style::Theme,
+) -> Element<'a, Message> {
+    let gain_label = if strip.gain() == 0.0 {
+        String::from("Gain: -inf dB")
+    } else {
+        format!("Gain: {:+.1} dB", strip.gain_db)
+    };
+    let pan_label = match (strip.pan * 100.0).round() as i32 {
+        0 => String::from("Pan: C"),
+        pan if pan < 0 => format!("Pan: L{}", -pan),
+        pan => format!("Pan: R{}", pan),
+    };
+
+    let column = Column::new()
+        .spacing(5)
+        .width(Length::Units(STRIP_WIDTH))
+        .push(Text::new(gain_label).size(16))
+        .push(
+            Slider::new(
+                &mut state.gain_slider,
+                Strip::MIN_GAIN_DB..=Strip::MAX_GAIN_DB,
+                strip.gain_db,
+                move |gain_db| Message::GainChanged(track, gain_db),
+            )
+            .step(0.5)
+            .style(theme),
+        )
+        .push(Text::new(pan_label).size(16))
+        .push(
+            Slider::new(&mut state.pan_slider, -1.0..=1.0, strip.pan, move |pan| {
+                Message::PanChanged(track, pan)
+            })
+            .step(0.01)
+            .style(theme),
+        )
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(
+                    Checkbox::new(strip.mute, "Mute", move |mute| {
+                        Message::MuteToggled(track, mute)
+                    })
+                    .size(16)
+                    .text_size(16)
+                    .style(theme),
+                )
+                .push(
+                    Checkbox::new(strip.solo, "Solo", move |solo| {
+                        Message::SoloToggled(track, solo)
+                    })
+                    .size(16)
+                    .text_size(16)
+                    .style(theme),
+                ),
+        );
+
+    match loudness {
+        Some(loudness) => column.push(loudness_readout(loudness)).into(),
+        None => column.into(),
+    }
+}
+
+// Loudness readings in two short lines: the windowed levels, then the integrated level, range and
+// true peak.
+fn loudness_readout<'a>(loudness: &Loudness) -> Column<'a, Message> {
+    let level = |level: f32| {
+        if level.is_finite() {
+            format!("{:.1}", level)
+        } else {
+            String::from("-inf")
+        }
+    };
+    Column::new()
+        .spacing(2)
+        .push(
+            Text::new(format!(
+                "M {}  S {} LUFS",
+                level(loudness.momentary),
+                level(loudness.short_term)
+            ))
+            .size(12),
+        )
+        .push(
+            Text::new(format!(
+                "I {}  LRA {:.1}  TP {}",
+                level(loudness.integrated),
+                loudness.range,
+                level(loudness.true_peak)
+            ))
+            .size(12),
+        )
+}
+
+// The selected track's effect chain, with a control for every parameter.
+fn effects_panel<'a>(
+    chain: &Chain,
+    states: &'a mut Vec<EffectState>,
+    add_effect: &'a mut pick_list::State<&'static str>,
+    names: Vec<&'static str>,
+    theme: style::Theme,
+) -> Element<'a, Message> {
+    states.resize_with(chain.len(), EffectState::default);
+
+    let mut column = Column::new().spacing(10).push(Text::new("Effects:"));
+    for (i, (effect, state)) in chain.iter().zip(states.iter_mut()).enumerate() {
+        let mut up_button = Button::new(&mut state.up_button, Text::new("Up"))
+            .padding(5)
+            .style(theme);
+        if i > 0 {
+            up_button = up_button.on_press(Message::EffectMovedUp(i));
+        }
+        let remove_button = Button::new(&mut state.remove_button, Text::new("Remove"))
+            .padding(5)
+            .on_press(Message::EffectRemoved(i))
+            .style(theme);
+
+        column = column.push(
+            Row::new()
+                .spacing(5)
+                .align_items(Align::Center)
+                .push(Text::new(format!("{}. {}", i + 1, effect.name())).width(Length::Fill))
+                .push(up_button)
+                .push(remove_button),
+        );
+
+        state
+            .parameter_sliders
+            .resize_with(effect.parameters().len(), slider::State::default);
+        for (p, (parameter, slider_state)) in effect
+            .parameters()
+            .iter()
+            .zip(state.parameter_sliders.iter_mut())
+            .enumerate()
+        {
+            let value = effect.parameter(p);
+            let decimals = if parameter.step >= 1.0 { 0 } else { 2 };
+            column = column
+                .push(
+                    Text::new(format!(
+                        "{}: {:.*} {}",
+                        parameter.name, decimals, value, parameter.unit
+                    ))
+                    .size(16),
+                )
+                .push(
+                    Slider::new(
+                        slider_state,
+                        parameter.min..=parameter.max,
+                        value,
+                        move |value| Message::EffectParameterChanged(i, p, value),
+                    )
+                    .step(parameter.step)
+                    .style(theme),
+                );
+        }
+    }
+
+    column
+        .push(PickList::new(add_effect, names, None, Message::EffectAdded).style(theme))
+        .into()
+}
+
+// The colour of the full band decay curve.
+const FULL_BAND_COLOR: Color = Color::from_rgb(1.0, 0.55, 0.1);
+
+// The colour of an octave band's decay curve, from blue for the lowest band to green for the
+// highest.
+fn band_color(band: usize) -> Color {
+    let t = band as f32 / (acoustics::OCTAVE_BANDS.len() - 1) as f32;
+    Color::from_rgb(0.2, 0.4 + 0.5 * t, 1.0 - 0.5 * t)
+}
+
+// The decay curves and parameters of an impulse response, one row per band.
+fn acoustics_panel<'a>(name: &str, acoustics: &'a Acoustics) -> Element<'a, Message> {
+    let mut decay = Decay::new(acoustics::DECAY_STEP);
+    for (i, band) in acoustics.bands.iter().enumerate().skip(1).rev() {
+        decay = decay.push(&band.decay, band_color(i - 1));
+    }
+    if let Some(full) = acoustics.bands.first() {
+        decay = decay.push(&full.decay, FULL_BAND_COLOR);
+    }
+
+    let seconds = |time: Option<f32>| time.map_or(String::from("-"), |t| format!("{:.2}", t));
+    let decibels = |level: Option<f32>| level.map_or(String::from("-"), |l| format!("{:.1}", l));
+    let row = |cells: [String; 8]| {
+        cells.iter().fold(Row::new().spacing(2), |row, cell| {
+            row.push(Text::new(cell.as_str()).size(12).width(Length::Fill))
+        })
+    };
+
+    let mut table = Column::new().spacing(4).push(row([
+        String::from("Hz"),
+        String::from("EDT"),
+        String::from("T20"),
+        String::from("T30"),
+        String::from("C50"),
+        String::from("C80"),
+        String::from("D50"),
+        String::from("Ts"),
+    ]));
+    for band in acoustics.bands.iter() {
+        table = table.push(row([
+            match band.frequency {
+                Some(f) if f >= 1000.0 => format!("{}k", f / 1000.0),
+                Some(f) => format!("{}", f),
+                None => String::from("All"),
+            },
+            seconds(band.edt),
+            seconds(band.t20),
+            seconds(band.t30),
+            decibels(band.c50),
+            decibels(band.c80),
+            format!("{:.0}%", band.d50 * 100.0),
+            format!("{:.0}", band.center_time * 1000.0),
+        ]));
+    }
+
+    Column::new()
+        .spacing(10)
+        .push(Text::new(format!(
+            "{}, direct sound at {:.1} ms",
+            name,
+            acoustics.onset * 1000.0
+        )))
+        .push(decay)
+        .push(table)
+        .push(Text::new("Times in seconds, C50 and C80 in dB, Ts in ms").size(12))
+        .into()
 }
 
 // The Events that the program will send and recieve to change values in the state.
@@ -54,19 +1103,88 @@ enum Message {
     PlayButtonPressed,
     PauseButtonPressed,
     AddNewChannelButtonPressed,
+    RemoveTrackButtonPressed,
     ImportAudioButtonPressed,
+    Undo,
+    Redo,
+    HistoryDepthChanged(u16),
+    Cut,
+    Copy,
+    Paste,
+    Delete,
+    TrimToSelection,
+    InsertSilence,
+    SilenceLengthChanged(f32),
+    ErrorDismissed,
+    OpenProjectButtonPressed,
+    SaveProjectButtonPressed,
+    BitDepthChanged(BitDepth),
+    SampleRateChanged(u32),
+    ExportTrackButtonPressed,
+    ExportMixButtonPressed,
+    TrackSelected(usize),
+    SelectionChanged(usize, Range<usize>),
+    Navigated(Navigate),
+    Tick,
+    Seek(f64),
+    RecordButtonPressed,
+    GainChanged(usize, f32),
+    PanChanged(usize, f32),
+    MuteToggled(usize, bool),
+    SoloToggled(usize, bool),
+    PanLawChanged(PanLaw),
+    EffectAdded(&'static str),
+    EffectRemoved(usize),
+    EffectMovedUp(usize),
+    EffectParameterChanged(usize, usize, f32),
+    ArmToggled(bool),
+    ImpulseTrackSelected(TrackChoice),
+    SweepDurationChanged(f32),
+    SweepGainChanged(f32),
+    GenerateSweepButtonPressed,
+    DeconvolveButtonPressed,
+    LoopbackButtonPressed,
+    FindOnset,
+    Prepare(Preparation),
+    ApplyPreview,
+    DiscardPreview,
+    FadeLengthChanged(f32),
+    NormalizeLevelChanged(f32),
+    AnalyseTrackLoudness,
+    AnalyseMixLoudness,
+    AnalyseButtonPressed,
+    ExportAcousticsButtonPressed,
+    ConvolveButtonPressed,
+    AddReverbButtonPressed,
+    ResetZoomButtonPressed,
+    FftSizeChanged(usize),
+    HopSizeChanged(usize),
+    WindowChanged(Window),
+    DbFloorChanged(f32),
+    DbCeilingChanged(f32),
 }
 
 // The app itself
-impl<T> Sandbox for State<T>
+impl<T> Application for State<T>
 where
     T: Sample,
     T: Default,
-    T: hound::Sample,
+    T: Send + 'static,
 {
+    type Executor = executor::Default;
     type Message = Message;
-    fn new() -> Self {
-        State::default()
+    type Flags = ();
+
+    fn new(_flags: ()) -> (Self, Command<Message>) {
+        (
+            State {
+                silence_length: 1.0,
+                fade_length: 0.1,
+                normalize_level: -1.0,
+                ..State::default()
+            },
+            Command::none(),
+        )
     }
 
     fn title(&self) -> String {
@@ -74,67 +1192,313 @@ where
     }
 
     // Will be triggered when a visual component is updated
-    fn update(&mut self, message: Message) {
+    fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
         match message {
             Message::ThemeChanged(theme) => self.theme = theme,
-            Message::PlayButtonPressed => self.audio_playing = true,
-            Message::PauseButtonPressed => self.audio_playing = false,
+            Message::PlayButtonPressed => match self.play() {
+                Ok(()) => self.audio_playing = true,
+                Err(e) => self.error = Some(e),
+            },
+            Message::PauseButtonPressed => {
+                if let Err(e) = self.pause() {
+                    self.error = Some(e);
+                }
+                self.audio_playing = false
+            }
             Message::AddNewChannelButtonPressed => {
-                self.channels.push(Channel::new());
-                self.spectrograms.push(Spectrogram::<T>::new(
-                    self.channels[self.channels.len() - 1].assign_sender(),
-                ))
+                let index = self.tracks.len();
+                self.edit(Edit::insert_tracks(
+                    index,
+                    vec![Channel::new().with_spectrogram()],
+                ));
+                self.selected_track = index;
             }
+            Message::RemoveTrackButtonPressed => {
+                if self.selected_track < self.tracks.len() {
+                    self.edit(Edit::remove_tracks(self.selected_track, 1));
+                }
+            }
+            Message::Undo => self.undo(),
+            Message::Redo => self.redo(),
+            Message::HistoryDepthChanged(depth) => self.history.set_depth(depth as usize),
+            Message::Cut => {
+                if let Some(selection) = self.selected_samples() {
+                    self.clipboard = self.tracks.copy(&selection);
+                    self.edit_samples(Edit::delete(&self.tracks, &selection));
+                }
+            }
+            Message::Copy => {
+                if let Some(selection) = self.selected_samples() {
+                    self.clipboard = self.tracks.copy(&selection);
+                }
+            }
+            Message::Paste => {
+                if let (Some(selection), Some(clip)) = (self.cursor(), &self.clipboard) {
+                    let edit = Edit::paste(&self.tracks, &selection, clip);
+                    self.edit_samples(edit);
+                }
+            }
+            Message::Delete => {
+                if let Some(selection) = self.selected_samples() {
+                    self.edit_samples(Edit::delete(&self.tracks, &selection));
+                }
+            }
+            Message::TrimToSelection => {
+                if let Some(selection) = self.selected_samples() {
+                    self.edit_samples(Edit::trim(&self.tracks, &selection));
+                }
+            }
+            Message::InsertSilence => {
+                if let Some(selection) = self.cursor() {
+                    let rate = self.tracks.channels[selection.track].info.sample_rate;
+                    let len = (self.silence_length * rate as f32) as usize;
+                    self.edit_samples(Edit::insert_silence(
+                        &self.tracks,
+                        selection.track,
+                        selection.range.start,
+                        len,
+                    ));
+                }
+            }
+            Message::SilenceLengthChanged(length) => self.silence_length = length,
             Message::ImportAudioButtonPressed => {
-                let channel_out = Channel::<T>::new();
-
-                let file = FileDialog::new()
-                    .set_location("~")
-                    .add_filter("FLAC Audio File", &["flac"])
-                    .add_filter("MPEG-3 Audio File", &["mp3"])
-                    .add_filter("Ogg-Vorbis Audio File", &["ogg"])
-                    .add_filter("WAV Audio File", &["wav"])
-                    .show_open_single_file()
-                    .unwrap();
-
-                if file.is_some() {
-                    let file_out = file.unwrap();
-                    let samples = match file_out.extension().unwrap().to_str().unwrap() {
-                        "wav" => {
-                            let reader = hound::WavReader::open(file_out.clone()).unwrap();
-                            reader.into_samples::<T>().filter_map(Result::ok)
-                        }
-                        _ => unimplemented!(),
-                    }
-                    .collect::<Vec<T>>();
-                    println!("Opening from {:?}", &file_out);
-
-                    self.channels.push(channel_out);
-
-                    let mut spectrogram_out = Spectrogram::<T>::new(
-                        self.channels[self.channels.len() - 1].assign_sender(),
-                    );
-
-                    for t in samples {
-                        self.channels[self.channels.len() - 1]
-                            .samples
-                            .lock()
-                            .unwrap()
-                            .push(t);
+                if let Err(e) = self.import_audio() {
+                    self.error = Some(e);
+                }
+            }
+            Message::ErrorDismissed => self.error = None,
+            Message::OpenProjectButtonPressed => {
+                if let Err(e) = self.open_project() {
+                    self.error = Some(e);
+                }
+            }
+            Message::SaveProjectButtonPressed => {
+                if let Err(e) = self.save_project() {
+                    self.error = Some(e);
+                }
+            }
+            Message::BitDepthChanged(bit_depth) => self.export_settings.bit_depth = bit_depth,
+            Message::SampleRateChanged(sample_rate) => {
+                self.export_settings.sample_rate = sample_rate
+            }
+            Message::ExportTrackButtonPressed => {
+                if let Some(channel) = self.tracks.channels.get(self.selected_track) {
+                    if let Err(e) = self.export(vec![channel.mixer_source()]) {
+                        self.error = Some(e);
                     }
-
-                    spectrogram_out.load(
-                        self.channels[self.channels.len() - 1].samples.clone(),
-                        BufferSize::All,
-                    );
-
-                    self.spectrograms.push(spectrogram_out)
                 }
             }
+            Message::ExportMixButtonPressed => {
+                let sources = self
+                    .tracks
+                    .channels
+                    .iter()
+                    .map(Channel::mixer_source)
+                    .collect();
+                if let Err(e) = self.export(sources) {
+                    self.error = Some(e);
+                }
+            }
+            Message::TrackSelected(i) => self.selected_track = i,
+            Message::SelectionChanged(track, range) => {
+                self.selected_track = track;
+                self.selection = Some(Selection { track, range });
+            }
+            Message::Navigated(navigate) => {
+                let duration = self.duration();
+                self.viewport.navigate(navigate, duration)
+            }
+            Message::ResetZoomButtonPressed => self.viewport = Viewport::default(),
+            Message::Tick => {
+                self.check_streams();
+                if let Some(recorder) = self.recorder.as_mut().filter(|r| r.is_recording()) {
+                    recorder.collect();
+                }
+                if let Some(engine) = &self.engine {
+                    self.playhead = engine.position() as f64 / engine.sample_rate() as f64;
+                    self.audio_playing = engine.is_playing();
+                    self.meters = Some(engine.loudness());
+                }
+            }
+            Message::RecordButtonPressed => {
+                let result = if self.recording.is_some() {
+                    self.stop_recording()
+                } else {
+                    self.record()
+                };
+                if let Err(e) = result {
+                    self.error = Some(e);
+                }
+            }
+            Message::GainChanged(track, gain_db) => {
+                self.update_strip(track, |strip| strip.gain_db = gain_db)
+            }
+            Message::PanChanged(track, pan) => self.update_strip(track, |strip| strip.pan = pan),
+            Message::MuteToggled(track, mute) => {
+                self.update_strip(track, |strip| strip.mute = mute)
+            }
+            Message::SoloToggled(track, solo) => {
+                self.update_strip(track, |strip| strip.solo = solo)
+            }
+            Message::PanLawChanged(pan_law) => {
+                self.update_strip(self.selected_track, |strip| strip.pan_law = pan_law)
+            }
+            Message::EffectAdded(name) => {
+                if let (Some(channel), Some(effect)) = (
+                    self.tracks.channels.get(self.selected_track),
+                    self.registry.create(name),
+                ) {
+                    let index = channel.effects.lock().unwrap().len();
+                    self.edit(Edit::InsertEffect {
+                        track: self.selected_track,
+                        index,
+                        stash: Some(effect),
+                    });
+                }
+            }
+            Message::EffectRemoved(index) => self.edit(Edit::RemoveEffect {
+                track: self.selected_track,
+                index,
+                stash: None,
+            }),
+            Message::EffectMovedUp(index) => self.edit(Edit::MoveEffect {
+                track: self.selected_track,
+                from: index,
+                to: index - 1,
+            }),
+            Message::EffectParameterChanged(effect, parameter, value) => {
+                let before = self
+                    .tracks
+                    .channels
+                    .get(self.selected_track)
+                    .and_then(|channel| {
+                        let effects = channel.effects.lock().unwrap();
+                        effects
+                            .get(effect)
+                            .map(|effect| effect.parameter(parameter))
+                    });
+                if let Some(before) = before {
+                    self.edit(Edit::EffectParameter {
+                        track: self.selected_track,
+                        effect,
+                        parameter,
+                        before,
+                        after: value,
+                    });
+                }
+            }
+            Message::ImpulseTrackSelected(choice) => self.impulse_track = Some(choice.index),
+            Message::ConvolveButtonPressed => self.convolve(),
+            Message::SweepDurationChanged(duration) => self.sweep.duration = duration,
+            Message::SweepGainChanged(gain_db) => self.sweep.gain_db = gain_db,
+            Message::GenerateSweepButtonPressed => self.generate_sweep(),
+            Message::DeconvolveButtonPressed => self.deconvolve(),
+            Message::FindOnset => self.find_onset(),
+            Message::Prepare(preparation) => self.preview(preparation),
+            Message::ApplyPreview => self.apply_preview(),
+            Message::DiscardPreview => self.discard_preview(),
+            Message::FadeLengthChanged(length) => self.fade_length = length,
+            Message::NormalizeLevelChanged(level) => self.normalize_level = level,
+            Message::AnalyseTrackLoudness => {
+                if let Some(channel) = self.tracks.channels.get(self.selected_track) {
+                    let mut source = self.sources().swap_remove(self.selected_track);
+                    // Measured as the track is mixed, even while it is muted
+                    source.strip.mute = false;
+                    self.loudness =
+                        Some((channel.name.clone(), self.analyse_loudness(vec![source])));
+                }
+            }
+            Message::AnalyseMixLoudness => {
+                self.loudness = Some((String::from("Mix"), self.analyse_loudness(self.sources())));
+            }
+            Message::AnalyseButtonPressed => {
+                if let Some(channel) = self.tracks.channels.get(self.selected_track) {
+                    let acoustics =
+                        Acoustics::analyse(&channel.float_samples(), channel.info.sample_rate);
+                    self.acoustics = Some((channel.name.clone(), acoustics));
+                }
+            }
+            Message::ExportAcousticsButtonPressed => {
+                if let Err(e) = self.export_acoustics() {
+                    self.error = Some(e);
+                }
+            }
+            Message::LoopbackButtonPressed => {
+                if let Err(e) = self.loopback() {
+                    self.error = Some(e);
+                }
+            }
+            Message::AddReverbButtonPressed => self.add_reverb(),
+            Message::ArmToggled(armed) => {
+                self.armed = if armed {
+                    Some(self.selected_track)
+                } else {
+                    None
+                }
+            }
+            Message::Seek(time) => {
+                self.playhead = time.clamp(0.0, self.duration());
+                if let Some(engine) = &self.engine {
+                    engine.seek((self.playhead * engine.sample_rate() as f64) as u64);
+                }
+            }
+            Message::FftSizeChanged(size) => self.update_selected_spectrogram(|s| s.fft_size(size)),
+            Message::HopSizeChanged(size) => self.update_selected_spectrogram(|s| s.hop_size(size)),
+            Message::WindowChanged(window) => {
+                self.update_selected_spectrogram(|s| s.window(window))
+            }
+            Message::DbFloorChanged(floor) => self.update_selected_spectrogram(|s| {
+                let ceiling = s.settings().db_ceiling;
+                s.db_range(floor, ceiling)
+            }),
+            Message::DbCeilingChanged(ceiling) => self.update_selected_spectrogram(|s| {
+                let floor = s.settings().db_floor;
+                s.db_range(floor, ceiling)
+            }),
         }
+        Command::none()
     }
 
-    fn view(&mut self) -> Element<Message> {
+    fn subscription(&self) -> Subscription<Message> {
+        let shortcuts = iced_native::subscription::events_with(shortcut);
+        if self.audio_playing {
+            // Follows the engine's position to animate the playhead
+            Subscription::batch(vec![
+                shortcuts,
+                time::every(Duration::from_millis(30)).map(|_| Message::Tick),
+            ])
+        } else if self.recording.is_some() {
+            // Moves what was recorded into the recording track, redrawing it as it grows
+            Subscription::batch(vec![
+                shortcuts,
+                time::every(Duration::from_millis(100)).map(|_| Message::Tick),
+            ])
+        } else {
+            shortcuts
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        let duration = self.duration();
+
+        // What the edit buttons can act on
+        let has_selection = self.selected_samples().is_some();
+        let has_cursor = has_selection || self.selected_track < self.tracks.len();
+        let can_paste = has_cursor && self.clipboard.is_some();
+        let impulse_channels = self.impulse_channels().len();
+        let selection_label = match &self.selection {
+            Some(selection) => {
+                let rate = self.tracks.channels[selection.track].info.sample_rate as f32;
+                format!(
+                    "Selection: {:.3} s to {:.3} s on track {}",
+                    selection.range.start as f32 / rate,
+                    selection.range.end as f32 / rate,
+                    selection.track + 1
+                )
+            }
+            None => String::from("Nothing selected"),
+        };
+
         // The theme selector, automatically constructing radios from available `Theme` enums. (see ./style/mod.rs)
         let choose_theme = style::Theme::ALL.iter().fold(
             Column::new().spacing(10).push(Text::new("Choose a theme:")),
@@ -161,6 +1525,18 @@ where
             .on_press(Message::PauseButtonPressed)
             .style(self.theme);
 
+        let record_button = Button::new(
+            &mut self.record_button,
+            Text::new(if self.recording.is_some() {
+                "Stop recording"
+            } else {
+                "Record"
+            }),
+        )
+        .padding(10)
+        .on_press(Message::RecordButtonPressed)
+        .style(self.theme);
+
         let add_new_channel_button = Button::new(
             &mut self.add_new_channel_button,
             Text::new("Add new channel"),
@@ -169,60 +1545,684 @@ where
         .on_press(Message::AddNewChannelButtonPressed)
         .style(self.theme);
 
+        let mut remove_track_button =
+            Button::new(&mut self.remove_track_button, Text::new("Remove track"))
+                .padding(10)
+                .style(self.theme);
+        if self.selected_track < self.tracks.len() {
+            remove_track_button = remove_track_button.on_press(Message::RemoveTrackButtonPressed);
+        }
+
         let import_audio_button =
             Button::new(&mut self.import_audio_button, Text::new("Import audio"))
                 .padding(10)
                 .on_press(Message::ImportAudioButtonPressed)
                 .style(self.theme);
 
-        let sidebar = Scrollable::new(&mut self.sidebar_scroll)
-            .style(self.theme)
+        let history_depth = Slider::new(
+            &mut self.history_depth_slider,
+            10..=1000,
+            self.history.depth() as u16,
+            Message::HistoryDepthChanged,
+        )
+        .step(10)
+        .style(self.theme);
+
+        let mut sidebar_content = Column::new()
+            .spacing(20)
+            .padding(20)
+            .width(Length::Units(300))
+            .push(choose_theme)
             .push(
                 Column::new()
-                    .spacing(20)
-                    .padding(20)
-                    .width(Length::Units(300))
-                    .push(choose_theme),
+                    .spacing(10)
+                    .push(Text::new(format!(
+                        "Undo history: {} steps",
+                        self.history.depth()
+                    )))
+                    .push(history_depth),
             );
 
-        let samples_clone: Vec<Arc<Mutex<Vec<T>>>> =
-            self.channels.iter().map(|c| c.samples.clone()).collect();
+        // The analysis settings of the selected track
+        if let Some(spectrogram) = self.tracks.spectrograms.get(self.selected_track) {
+            let settings = spectrogram.settings();
+            let theme = self.theme;
+            let selected_track = self.selected_track;
+
+            let choose_track = self.tracks.channels.iter().enumerate().fold(
+                Column::new().spacing(10).push(Text::new("Track:")),
+                |column, (i, channel)| {
+                    column.push(
+                        Radio::new(
+                            i,
+                            &format!("{}: {}", i + 1, channel.name),
+                            Some(selected_track),
+                            Message::TrackSelected,
+                        )
+                        .style(theme),
+                    )
+                },
+            );
+
+            let fft_size = PickList::new(
+                &mut self.fft_size_pick_list,
+                &spectrogram::Settings::FFT_SIZES[..],
+                Some(settings.fft_size),
+                Message::FftSizeChanged,
+            )
+            .style(theme);
+
+            let hop_size = PickList::new(
+                &mut self.hop_size_pick_list,
+                &spectrogram::Settings::HOP_SIZES[..],
+                Some(settings.hop_size),
+                Message::HopSizeChanged,
+            )
+            .style(theme);
+
+            let window = PickList::new(
+                &mut self.window_pick_list,
+                &Window::ALL[..],
+                Some(settings.window),
+                Message::WindowChanged,
+            )
+            .style(theme);
+
+            let db_floor = Slider::new(
+                &mut self.db_floor_slider,
+                -160.0..=-20.0,
+                settings.db_floor,
+                Message::DbFloorChanged,
+            )
+            .step(1.0)
+            .style(theme);
+
+            let db_ceiling = Slider::new(
+                &mut self.db_ceiling_slider,
+                -60.0..=20.0,
+                settings.db_ceiling,
+                Message::DbCeilingChanged,
+            )
+            .step(1.0)
+            .style(theme);
+
+            let arm = Checkbox::new(
+                self.armed == Some(selected_track),
+                "Record into this track",
+                Message::ArmToggled,
+            )
+            .style(theme);
+
+            let pan_law = PickList::new(
+                &mut self.pan_law_pick_list,
+                &PanLaw::ALL[..],
+                Some(self.tracks.channels[selected_track].strip.pan_law),
+                Message::PanLawChanged,
+            )
+            .style(theme);
+
+            let effects = effects_panel(
+                &self.tracks.channels[selected_track].effects.lock().unwrap(),
+                &mut self.effect_states,
+                &mut self.add_effect_pick_list,
+                self.registry.names(),
+                theme,
+            );
+
+            let track_choices: Vec<TrackChoice> = self
+                .tracks
+                .channels
+                .iter()
+                .enumerate()
+                .map(|(index, channel)| TrackChoice {
+                    index,
+                    name: channel.name.clone(),
+                })
+                .collect();
+            let impulse_choice = self
+                .impulse_track
+                .and_then(|track| track_choices.get(track).cloned());
+            let has_impulse = impulse_choice.is_some();
+            let impulse = PickList::new(
+                &mut self.impulse_pick_list,
+                track_choices,
+                impulse_choice,
+                Message::ImpulseTrackSelected,
+            )
+            .style(theme);
+
+            let mut convolve_button = Button::new(&mut self.convolve_button, Text::new("Convolve"))
+                .padding(5)
+                .style(theme);
+            let mut add_reverb_button =
+                Button::new(&mut self.add_reverb_button, Text::new("Add as effect"))
+                    .padding(5)
+                    .style(theme);
+            if has_impulse {
+                convolve_button = convolve_button.on_press(Message::ConvolveButtonPressed);
+                add_reverb_button = add_reverb_button.on_press(Message::AddReverbButtonPressed);
+            }
+            let mut impulse_section = Column::new()
+                .spacing(10)
+                .push(Text::new("Impulse response:"))
+                .push(impulse)
+                .push(
+                    Row::new()
+                        .spacing(5)
+                        .push(convolve_button)
+                        .push(add_reverb_button),
+                );
+            if impulse_channels > 1 {
+                impulse_section = impulse_section.push(Text::new(format!(
+                    "As an effect, all {} channels are used",
+                    impulse_channels
+                )));
+            }
+
+            sidebar_content = sidebar_content
+                .push(choose_track)
+                .push(arm)
+                .push(
+                    Column::new()
+                        .spacing(10)
+                        .push(Text::new("Pan law:"))
+                        .push(pan_law),
+                )
+                .push(effects)
+                .push(impulse_section)
+                .push(
+                    Column::new()
+                        .spacing(10)
+                        .push(Text::new("FFT size:"))
+                        .push(fft_size)
+                        .push(Text::new("Hop size:"))
+                        .push(hop_size)
+                        .push(Text::new("Window:"))
+                        .push(window)
+                        .push(Text::new(format!("Floor: {} dB", settings.db_floor)))
+                        .push(db_floor)
+                        .push(Text::new(format!("Ceiling: {} dB", settings.db_ceiling)))
+                        .push(db_ceiling),
+                );
+        }
+
+        // Destructive edits on the selection
+        let theme = self.theme;
+        let edit_button = |state, label, message, enabled| {
+            let button = Button::new(state, Text::new(label)).padding(5).style(theme);
+            if enabled {
+                button.on_press(message)
+            } else {
+                button
+            }
+        };
+        let cut_button = edit_button(&mut self.cut_button, "Cut", Message::Cut, has_selection);
+        let copy_button = edit_button(&mut self.copy_button, "Copy", Message::Copy, has_selection);
+        let paste_button = edit_button(&mut self.paste_button, "Paste", Message::Paste, can_paste);
+        let delete_button = edit_button(
+            &mut self.delete_button,
+            "Delete",
+            Message::Delete,
+            has_selection,
+        );
+        let trim_button = edit_button(
+            &mut self.trim_button,
+            "Trim",
+            Message::TrimToSelection,
+            has_selection,
+        );
+        let insert_silence_button = edit_button(
+            &mut self.insert_silence_button,
+            "Insert silence",
+            Message::InsertSilence,
+            has_cursor,
+        );
 
-        let col: Element<_> = self
-            .spectrograms
+        let view_label = format!(
+            "View: {:.3} s to {:.3} s",
+            self.viewport.start,
+            self.viewport.start + self.viewport.duration(duration)
+        );
+        let mut reset_zoom_button =
+            Button::new(&mut self.reset_zoom_button, Text::new("Reset zoom"))
+                .padding(5)
+                .style(theme);
+        if self.viewport != Viewport::default() {
+            reset_zoom_button = reset_zoom_button.on_press(Message::ResetZoomButtonPressed);
+        }
+
+        let silence_length = Slider::new(
+            &mut self.silence_length_slider,
+            0.1..=10.0,
+            self.silence_length,
+            Message::SilenceLengthChanged,
+        )
+        .step(0.1)
+        .style(self.theme);
+
+        sidebar_content = sidebar_content.push(
+            Column::new()
+                .spacing(10)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Text::new(view_label).width(Length::Fill))
+                        .push(reset_zoom_button),
+                )
+                .push(Text::new(selection_label))
+                .push(
+                    Row::new()
+                        .spacing(5)
+                        .push(cut_button)
+                        .push(copy_button)
+                        .push(paste_button)
+                        .push(delete_button)
+                        .push(trim_button),
+                )
+                .push(Text::new(format!("Silence: {:.1} s", self.silence_length)))
+                .push(silence_length)
+                .push(insert_silence_button),
+        );
+
+        let sweep_duration = Slider::new(
+            &mut self.sweep_duration_slider,
+            Sweep::MIN_DURATION..=Sweep::MAX_DURATION,
+            self.sweep.duration,
+            Message::SweepDurationChanged,
+        )
+        .step(0.5)
+        .style(theme);
+
+        let sweep_gain = Slider::new(
+            &mut self.sweep_gain_slider,
+            Sweep::MIN_GAIN_DB..=Sweep::MAX_GAIN_DB,
+            self.sweep.gain_db,
+            Message::SweepGainChanged,
+        )
+        .step(1.0)
+        .style(theme);
+
+        let generate_sweep_button = edit_button(
+            &mut self.generate_sweep_button,
+            "Generate",
+            Message::GenerateSweepButtonPressed,
+            true,
+        );
+        let deconvolve_button = edit_button(
+            &mut self.deconvolve_button,
+            "Deconvolve",
+            Message::DeconvolveButtonPressed,
+            self.selected_track < self.tracks.len(),
+        );
+        let loopback_button = edit_button(
+            &mut self.loopback_button,
+            "Loopback",
+            Message::LoopbackButtonPressed,
+            true,
+        );
+
+        sidebar_content = sidebar_content.push(
+            Column::new()
+                .spacing(10)
+                .push(Text::new("Sweep measurement:"))
+                .push(Text::new(format!("Duration: {:.1} s", self.sweep.duration)))
+                .push(sweep_duration)
+                .push(Text::new(format!("Level: {:.0} dBFS", self.sweep.gain_db)))
+                .push(sweep_gain)
+                .push(
+                    Row::new()
+                        .spacing(5)
+                        .push(generate_sweep_button)
+                        .push(deconvolve_button)
+                        .push(loopback_button),
+                ),
+        );
+
+        let has_track = self.selected_track < self.tracks.len();
+        let find_onset_button = edit_button(
+            &mut self.find_onset_button,
+            "Find onset",
+            Message::FindOnset,
+            has_track,
+        );
+        let trim_pre_delay_button = edit_button(
+            &mut self.trim_pre_delay_button,
+            "Trim pre-delay",
+            Message::Prepare(Preparation::TrimPreDelay),
+            has_track,
+        );
+        let fade_tail_button = edit_button(
+            &mut self.fade_tail_button,
+            "Fade tail",
+            Message::Prepare(Preparation::FadeTail {
+                length: self.fade_length,
+            }),
+            has_track,
+        );
+        let normalize_button = edit_button(
+            &mut self.normalize_button,
+            "Normalize",
+            Message::Prepare(Preparation::Normalize {
+                peak_db: self.normalize_level,
+            }),
+            has_track,
+        );
+        let minimum_phase_button = edit_button(
+            &mut self.minimum_phase_button,
+            "Minimum phase",
+            Message::Prepare(Preparation::MinimumPhase),
+            has_track,
+        );
+        let fade_length = Slider::new(
+            &mut self.fade_length_slider,
+            0.01..=2.0,
+            self.fade_length,
+            Message::FadeLengthChanged,
+        )
+        .step(0.01)
+        .style(theme);
+        let normalize_level = Slider::new(
+            &mut self.normalize_level_slider,
+            -24.0..=0.0,
+            self.normalize_level,
+            Message::NormalizeLevelChanged,
+        )
+        .step(0.5)
+        .style(theme);
+
+        let mut impulse_tools = Column::new()
+            .spacing(10)
+            .push(Text::new("Impulse response tools:"))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(find_onset_button)
+                    .push(trim_pre_delay_button),
+            )
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(fade_tail_button)
+                    .push(normalize_button)
+                    .push(minimum_phase_button),
+            )
+            .push(Text::new(format!("Tail fade: {:.2} s", self.fade_length)))
+            .push(fade_length)
+            .push(Text::new(format!(
+                "Normalize to: {:.1} dBFS",
+                self.normalize_level
+            )))
+            .push(normalize_level);
+        if let Some(preview) = &self.preview {
+            let apply_button = edit_button(
+                &mut self.apply_preview_button,
+                "Apply",
+                Message::ApplyPreview,
+                true,
+            );
+            let discard_button = edit_button(
+                &mut self.discard_preview_button,
+                "Discard",
+                Message::DiscardPreview,
+                true,
+            );
+            impulse_tools = impulse_tools
+                .push(Text::new(format!(
+                    "Previewing {} on track {}",
+                    preview.preparation.to_string().to_lowercase(),
+                    preview.track + 1
+                )))
+                .push(
+                    Row::new()
+                        .spacing(5)
+                        .push(apply_button)
+                        .push(discard_button),
+                );
+        }
+        sidebar_content = sidebar_content.push(impulse_tools);
+
+        let analyse_track_loudness_button = edit_button(
+            &mut self.analyse_track_loudness_button,
+            "Analyse track",
+            Message::AnalyseTrackLoudness,
+            has_track,
+        );
+        let analyse_mix_loudness_button = edit_button(
+            &mut self.analyse_mix_loudness_button,
+            "Analyse mix",
+            Message::AnalyseMixLoudness,
+            true,
+        );
+        let mut loudness_section = Column::new().spacing(10).push(Text::new("Loudness:"));
+        if let Some((_, master)) = &self.meters {
+            loudness_section = loudness_section
+                .push(Text::new("Output"))
+                .push(loudness_readout(master));
+        }
+        if let Some((name, reading)) = &self.loudness {
+            loudness_section = loudness_section
+                .push(Text::new(format!("{} (whole file)", name)))
+                .push(loudness_readout(reading));
+        }
+        sidebar_content = sidebar_content.push(
+            loudness_section.push(
+                Row::new()
+                    .spacing(5)
+                    .push(analyse_track_loudness_button)
+                    .push(analyse_mix_loudness_button),
+            ),
+        );
+
+        let analyse_button = edit_button(
+            &mut self.analyse_button,
+            "Analyse track",
+            Message::AnalyseButtonPressed,
+            has_track,
+        );
+        let export_acoustics_button = edit_button(
+            &mut self.export_acoustics_button,
+            "Export JSON",
+            Message::ExportAcousticsButtonPressed,
+            self.acoustics.is_some(),
+        );
+
+        let mut room_acoustics = Column::new()
+            .spacing(10)
+            .push(Text::new("Room acoustics:"))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(analyse_button)
+                    .push(export_acoustics_button),
+            );
+        if let Some((name, acoustics)) = &self.acoustics {
+            room_acoustics = room_acoustics.push(acoustics_panel(name, acoustics));
+        }
+        sidebar_content = sidebar_content.push(room_acoustics);
+
+        let bit_depth = PickList::new(
+            &mut self.bit_depth_pick_list,
+            &BitDepth::ALL[..],
+            Some(self.export_settings.bit_depth),
+            Message::BitDepthChanged,
+        )
+        .style(self.theme);
+
+        let sample_rate = PickList::new(
+            &mut self.sample_rate_pick_list,
+            &export::Settings::SAMPLE_RATES[..],
+            Some(self.export_settings.sample_rate),
+            Message::SampleRateChanged,
+        )
+        .style(self.theme);
+
+        let mut export_track_button =
+            Button::new(&mut self.export_track_button, Text::new("Export track"))
+                .padding(10)
+                .style(self.theme);
+        if self.selected_track < self.tracks.len() {
+            export_track_button = export_track_button.on_press(Message::ExportTrackButtonPressed);
+        }
+
+        let export_mix_button = Button::new(&mut self.export_mix_button, Text::new("Export mix"))
+            .padding(10)
+            .on_press(Message::ExportMixButtonPressed)
+            .style(self.theme);
+
+        sidebar_content = sidebar_content.push(
+            Column::new()
+                .spacing(10)
+                .push(Text::new("Export bit depth:"))
+                .push(bit_depth)
+                .push(Text::new("Export sample rate:"))
+                .push(sample_rate)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .push(export_track_button)
+                        .push(export_mix_button),
+                ),
+        );
+
+        let open_project_button =
+            Button::new(&mut self.open_project_button, Text::new("Open project"))
+                .padding(10)
+                .on_press(Message::OpenProjectButtonPressed)
+                .style(self.theme);
+
+        let save_project_button =
+            Button::new(&mut self.save_project_button, Text::new("Save project"))
+                .padding(10)
+                .on_press(Message::SaveProjectButtonPressed)
+                .style(self.theme);
+
+        let sidebar = Scrollable::new(&mut self.sidebar_scroll)
+            .style(self.theme)
+            .push(sidebar_content);
+
+        // Every track is drawn over the same stretch of time
+        let selection = self.selection.clone();
+        let viewport = self.viewport;
+        let playhead = self.playhead;
+        let meters = self.meters.as_ref().map(|(tracks, _)| tracks.clone());
+        let preview = self
+            .preview
+            .as_ref()
+            .map(|preview| (preview.track, preview.preparation, preview.samples.clone()));
+        self.strip_states
+            .resize_with(self.tracks.len(), StripState::default);
+        let mut col = Column::new();
+        for (i, ((channel, s), strip_state)) in self
+            .tracks
+            .channels
             .iter()
+            .zip(self.tracks.spectrograms.iter())
+            .zip(self.strip_states.iter_mut())
             .enumerate()
-            .fold(Column::new(), |acc, (i, s)| {
-                let mut cloned = s.clone();
-                cloned.load(samples_clone[i].clone(), BufferSize::All);
-                acc.push(s.clone())
-            })
-            .into();
+        {
+            let (samples, name) = match &preview {
+                Some((track, preparation, samples)) if *track == i => (
+                    samples.clone(),
+                    format!("{} (preview: {})", channel.name, preparation),
+                ),
+                _ => (channel.samples.clone(), channel.name.clone()),
+            };
+            let mut cloned = s.clone();
+            cloned.load(
+                samples,
+                viewport.buffersize(duration, channel.info.sample_rate),
+            );
+            let selected = selection
+                .as_ref()
+                .filter(|selection| selection.track == i)
+                .map(|selection| selection.range.clone());
+            let rate = channel.info.sample_rate as f64;
+
+            col = col.push(Text::new(name)).push(
+                Row::new()
+                    .spacing(10)
+                    .push(mixer_strip(
+                        i,
+                        &channel.strip,
+                        meters.as_ref().and_then(|meters| meters.get(i)),
+                        strip_state,
+                        theme,
+                    ))
+                    .push(
+                        cloned
+                            .frequencies(viewport.frequencies)
+                            .selection(selected)
+                            .playhead(Some((playhead * rate).round() as usize))
+                            .on_select(move |range| Message::SelectionChanged(i, range))
+                            .on_navigate(Message::Navigated),
+                    ),
+            );
+        }
+
+        let timeline = Timeline::new(
+            &mut self.timeline,
+            viewport.start,
+            viewport.duration(duration),
+            self.playhead,
+            Message::Seek,
+        );
 
         let spectrogram_display =
             iced_graphics::Scrollable::new(&mut self.spectrogram_display_scroll)
                 .style(self.theme)
                 .push(col);
 
-        let audio_playing_label = Text::new(if self.audio_playing {
-            "Currently playing audio"
-        } else {
-            "No audio playing"
-        });
-        let content = Column::new()
-            .padding(10)
+        let audio_playing_label = Text::new(format!(
+            "{} at {:.2} s",
+            if self.audio_playing {
+                "Playing"
+            } else {
+                "Stopped"
+            },
+            self.playhead
+        ));
+        let mut content = Column::new().padding(10);
+
+        if let Some(error) = &self.error {
+            let dismiss_error_button =
+                Button::new(&mut self.dismiss_error_button, Text::new("Dismiss"))
+                    .padding(5)
+                    .on_press(Message::ErrorDismissed)
+                    .style(self.theme);
+
+            content = content.push(
+                Container::new(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Text::new(error.to_string()).width(Length::Fill))
+                        .push(dismiss_error_button),
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .style(style::ErrorBanner),
+            );
+        }
+
+        let content = content
             .push(
                 Row::new()
                     .spacing(10)
                     .height(Length::Units(40))
                     .push(play_button)
                     .push(pause_button)
+                    .push(record_button)
                     .push(Rule::vertical(0).style(self.theme))
                     .align_items(Align::Center)
                     .push(audio_playing_label)
                     .push(Rule::vertical(0).style(self.theme))
                     .push(add_new_channel_button)
-                    .push(import_audio_button),
+                    .push(remove_track_button)
+                    .push(import_audio_button)
+                    .push(Rule::vertical(0).style(self.theme))
+                    .push(open_project_button)
+                    .push(save_project_button),
             )
             .push(Rule::horizontal(38).style(self.theme))
             .push(
@@ -234,13 +2234,19 @@ where
                             .spacing(10)
                             .push(Text::new(format!(
                                 "{} {}",
-                                self.spectrograms.len().to_string(),
-                                if self.spectrograms.len() == 1 {
+                                self.tracks.len(),
+                                if self.tracks.len() == 1 {
                                     "track"
                                 } else {
                                     "tracks"
                                 }
                             )))
+                            .push(
+                                Row::new()
+                                    .spacing(10)
+                                    .push(Space::with_width(Length::Units(STRIP_WIDTH)))
+                                    .push(timeline),
+                            )
                             .push(spectrogram_display),
                     )),
             );
@@ -257,3 +2263,82 @@ where
 pub fn main() -> iced::Result {
     State::<f32>::run(Settings::default())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_into_the_armed_track() {
+        let mut state = State::<f32>::default();
+        let mut channel = Channel::new();
+        channel.info = AudioInfo {
+            sample_rate: 48000,
+            channels: 1,
+            ..AudioInfo::default()
+        };
+        *channel.samples.lock().unwrap() = vec![0.25; 3];
+        state.edit(Edit::insert_tracks(0, vec![channel.with_spectrogram()]));
+        state.armed = Some(0);
+        state.recorder = Some(Recorder::null(48000, 2));
+
+        state.record().unwrap();
+        let recorder = state.recorder.as_mut().unwrap();
+        recorder.capture(&[0.5, 0.5, 1.0, 0.0]);
+        recorder.collect();
+        recorder.capture(&[-1.0, 0.0]);
+        // The recording track can't be edited until recording stops
+        state.edit(Edit::insert_tracks(
+            0,
+            vec![Channel::new().with_spectrogram()],
+        ));
+        assert!(matches!(state.error, Some(Error::Recording)));
+        state.stop_recording().unwrap();
+
+        assert_eq!(state.tracks.len(), 1);
+        assert_eq!(
+            *state.tracks.channels[0].samples.lock().unwrap(),
+            vec![0.25, 0.25, 0.25, 0.5, 0.5, -0.5]
+        );
+        // The recording is undone in one step
+        state.undo();
+        assert_eq!(state.tracks.channels[0].samples.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn opening_a_project_forgets_the_old_tracks() {
+        let folder =
+            std::env::temp_dir().join(format!("impulse-editor-open-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("session.impulse");
+
+        let mut state = State::<f32>::default();
+        let mut channel = Channel::new();
+        channel.info = AudioInfo {
+            sample_rate: 48000,
+            channels: 1,
+            ..AudioInfo::default()
+        };
+        *channel.samples.lock().unwrap() = vec![0.25; 3];
+        state.edit(Edit::insert_tracks(0, vec![channel.with_spectrogram()]));
+        state.write_project(&path).unwrap();
+
+        state.edit(Edit::insert_tracks(
+            1,
+            vec![Channel::new().with_spectrogram()],
+        ));
+        state.armed = Some(1);
+        state.impulse_track = Some(1);
+        state.load_project(&path).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(state.tracks.len(), 1);
+        assert_eq!(state.armed, None);
+        assert_eq!(state.impulse_track, None);
+        // Recording goes into a new track rather than one that is gone
+        state.recorder = Some(Recorder::null(48000, 1));
+        state.record().unwrap();
+        state.stop_recording().unwrap();
+        assert_eq!(state.tracks.len(), 2);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::dsp::{impulse, Biquad};
+use crate::Result;
+use serde::Serialize;
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// The centre frequencies of the octave bands analysed, in Hz. Bands reaching past the Nyquist
+/// frequency are left out.
+pub const OCTAVE_BANDS: [f32; 8] = [63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0];
+
+/// The time between points of a decay curve, in seconds.
+pub const DECAY_STEP: f32 = 0.001;
+
+// Decay curves are cut off this far down, in dB.
+const DECAY_FLOOR: f32 = -120.0;
+
+// The length of the windows the response is averaged over to find its noise floor, in seconds.
+const NOISE_WINDOW: f32 = 0.01;
+
+// How far the end of a response may still be falling, in dB, and be taken for noise.
+const NOISE_SLOPE: f64 = 3.0;
+
+// How many times the noise floor and where the decay meets it are estimated in turn.
+const NOISE_ITERATIONS: usize = 5;
+
+/// Room acoustic parameters of an impulse response, after ISO 3382-1.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Acoustics {
+    pub sample_rate: u32,
+    /// When the direct sound arrives, in seconds from the start of the impulse response. Every
+    /// time below is measured from here.
+    pub onset: f32,
+    /// The full band first, then each octave band from the lowest up.
+    pub bands: Vec<Band>,
+}
+
+/// The parameters of one frequency band.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Band {
+    /// The octave's centre frequency in Hz, or `None` for the full band.
+    pub frequency: Option<f32>,
+    /// Early decay time, from the first 10 dB of decay, in seconds.
+    pub edt: Option<f32>,
+    /// Reverberation time from the decay between -5 and -25 dB, in seconds.
+    pub t20: Option<f32>,
+    /// Reverberation time from the decay between -5 and -35 dB, in seconds.
+    pub t30: Option<f32>,
+    /// Clarity for speech: the energy of the first 50 ms against the rest, in dB. There is none
+    /// if either holds no energy.
+    pub c50: Option<f32>,
+    /// Clarity for music: the energy of the first 80 ms against the rest, in dB.
+    pub c80: Option<f32>,
+    /// Definition: the share of the energy arriving in the first 50 ms, from 0 to 1.
+    pub d50: f32,
+    /// The centre of gravity of the energy, in seconds.
+    pub center_time: f32,
+    /// The Schroeder backward-integrated decay curve, in dB relative to the total energy, with
+    /// one point every `DECAY_STEP`.
+    pub decay: Vec<f32>,
+}
+
+impl Acoustics {
+    /// Analyses an impulse response recorded at `sample_rate`.
+    ///
+    /// Each band's energy is integrated only up to where it sinks into its noise floor, with the
+    /// noise taken out. Responses that are still decaying at the end are taken to have no noise.
+    pub fn analyse(impulse: &[f32], sample_rate: u32) -> Self {
+        let rate = sample_rate.max(1);
+
+        let onset = impulse::onset(impulse).unwrap_or(0);
+
+        let nyquist = rate as f32 / 2.0;
+        let mut bands = vec![Band::analyse(None, &impulse[onset..], rate)];
+        for frequency in OCTAVE_BANDS
+            .iter()
+            .copied()
+            .filter(|frequency| frequency * 2f32.sqrt() < nyquist * 0.95)
+        {
+            let filtered = octave(impulse, frequency, rate);
+            bands.push(Band::analyse(Some(frequency), &filtered[onset..], rate));
+        }
+
+        Self {
+            sample_rate: rate,
+            onset: onset as f32 / rate as f32,
+            bands,
+        }
+    }
+
+    /// Writes the analysis as JSON.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+}
+
+impl Band {
+    fn analyse(frequency: Option<f32>, impulse: &[f32], sample_rate: u32) -> Self {
+        let rate = sample_rate as f32;
+        let energy: Vec<f64> = impulse.iter().map(|s| (*s as f64).powi(2)).collect();
+        let (end, noise) = noise_floor(&energy, sample_rate);
+        let energy = &energy[..end];
+
+        // Backward integration, with the noise the integral would otherwise pick up taken out
+        let mut remaining = vec![0.0; energy.len()];
+        let mut sum = 0.0;
+        for (i, e) in energy.iter().enumerate().rev() {
+            sum += (e - noise).max(0.0);
+            remaining[i] = sum;
+        }
+        let total = remaining.first().copied().unwrap_or(0.0);
+        let curve: Vec<f32> = remaining
+            .iter()
+            .map(|e| {
+                if total > 0.0 && *e > 0.0 {
+                    ((10.0 * (e / total).log10()) as f32).max(DECAY_FLOOR)
+                } else {
+                    DECAY_FLOOR
+                }
+            })
+            .collect();
+
+        let step = ((DECAY_STEP * rate) as usize).max(1);
+        let decay = curve.iter().step_by(step).copied().collect();
+
+        let within = |seconds: f32| -> f64 {
+            let split = ((seconds * rate) as usize).min(energy.len());
+            energy[..split].iter().sum()
+        };
+        let all: f64 = energy.iter().sum();
+        let clarity = |seconds: f32| -> Option<f32> {
+            let early = within(seconds);
+            let late = all - early;
+            if early > 0.0 && late > 0.0 {
+                Some((10.0 * (early / late).log10()) as f32)
+            } else {
+                None
+            }
+        };
+        let weighted: f64 = energy.iter().enumerate().map(|(i, e)| i as f64 * e).sum();
+
+        Self {
+            frequency,
+            edt: reverberation_time(&curve, 0.0, -10.0, rate),
+            t20: reverberation_time(&curve, -5.0, -25.0, rate),
+            t30: reverberation_time(&curve, -5.0, -35.0, rate),
+            c50: clarity(0.05),
+            c80: clarity(0.08),
+            d50: if all > 0.0 {
+                (within(0.05) / all) as f32
+            } else {
+                0.0
+            },
+            center_time: if all > 0.0 {
+                (weighted / all) as f32 / rate
+            } else {
+                0.0
+            },
+            decay,
+        }
+    }
+}
+
+// Where the response sinks into the noise, and the noise's energy per sample, found by iterative
+// truncation after Lundeby et al. (1995). The noise is first taken from the last tenth of the
+// response; a line fitted to the decay from the loudest point down to 10 dB above the noise meets
+// it where the response ends, and the noise is taken again from 5 dB of decay past there, but
+// never from less than the last tenth. That repeats until the end settles.
+//
+// If the last tenth is still falling there is no noise to find, as in a synthetic or faded out
+// response, and the whole response is used as it is.
+fn noise_floor(energy: &[f64], sample_rate: u32) -> (usize, f64) {
+    let window = ((NOISE_WINDOW * sample_rate as f32) as usize).max(1);
+    let means: Vec<f64> = energy
+        .chunks(window)
+        .map(|chunk| chunk.iter().sum::<f64>() / chunk.len() as f64)
+        .collect();
+    let tail = means.len() / 10;
+    if tail < 2 {
+        return (energy.len(), 0.0);
+    }
+    let last_tenth = means.len() - tail;
+    let mean = |from: usize| means[from..].iter().sum::<f64>() / (means.len() - from) as f64;
+    let decibels = |e: f64| 10.0 * e.max(f64::MIN_POSITIVE).log10();
+
+    let (slope, _) = fit(means[last_tenth..].iter().map(|mean| decibels(*mean)));
+    if -slope * tail as f64 > NOISE_SLOPE {
+        return (energy.len(), 0.0);
+    }
+
+    let loudest = (0..means.len())
+        .max_by(|a, b| means[*a].total_cmp(&means[*b]))
+        .unwrap_or(0);
+    let mut noise = mean(last_tenth);
+    let mut end = means.len();
+    for _ in 0..NOISE_ITERATIONS {
+        let above = decibels(noise) + 10.0;
+        let stop = means[loudest..]
+            .iter()
+            .position(|mean| decibels(*mean) <= above)
+            .map_or(means.len(), |i| loudest + i);
+        if stop < loudest + 2 {
+            break;
+        }
+        let (slope, intercept) = fit(means[loudest..stop].iter().map(|mean| decibels(*mean)));
+        if slope >= 0.0 {
+            break;
+        }
+        let crossing = (loudest as f64 + (decibels(noise) - intercept) / slope)
+            .clamp(loudest as f64 + 1.0, means.len() as f64) as usize;
+        let past = crossing + (-5.0 / slope).ceil() as usize;
+        noise = mean(past.min(last_tenth));
+        if crossing == end {
+            break;
+        }
+        end = crossing;
+    }
+    ((end * window).clamp(1, energy.len()), noise)
+}
+
+// The slope and intercept of a straight line fitted by least squares to `points`, against their
+// indices.
+fn fit(points: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (mut n, mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (i, y) in points.enumerate() {
+        let x = i as f64;
+        n += 1.0;
+        sx += x;
+        sy += y;
+        sxx += x * x;
+        sxy += x * y;
+    }
+    let slope = (n * sxy - sx * sy) / (n * sxx - sx * sx);
+    (slope, (sy - slope * sx) / n)
+}
+
+// Extrapolates the time a decay curve takes to fall by 60 dB from a straight line fitted to it
+// between `from` and `to` dB. There is none if the curve never falls as far as `to`.
+fn reverberation_time(curve: &[f32], from: f32, to: f32, sample_rate: f32) -> Option<f32> {
+    let start = curve.iter().position(|db| *db <= from)?;
+    let end = start + curve[start..].iter().position(|db| *db <= to)?;
+    if end <= start {
+        return None;
+    }
+
+    // Least squares over the points between the two levels, in dB per second
+    let (slope, _) = fit(curve[start..=end].iter().map(|db| *db as f64));
+    let slope = slope * sample_rate as f64;
+    if slope < 0.0 {
+        Some((-60.0 / slope) as f32)
+    } else {
+        None
+    }
+}
+
+// Filters out one octave around `frequency` with fourth-order Linkwitz-Riley high- and low-pass
+// filters at the band edges.
+fn octave(samples: &[f32], frequency: f32, sample_rate: u32) -> Vec<f32> {
+    let low = frequency as f64 / 2f64.sqrt();
+    let high = frequency as f64 * 2f64.sqrt();
+    let mut filters = [
+        Biquad::high_pass(low, FRAC_1_SQRT_2, sample_rate),
+        Biquad::high_pass(low, FRAC_1_SQRT_2, sample_rate),
+        Biquad::low_pass(high, FRAC_1_SQRT_2, sample_rate),
+        Biquad::low_pass(high, FRAC_1_SQRT_2, sample_rate),
+    ];
+    samples
+        .iter()
+        .map(|s| {
+            filters
+                .iter_mut()
+                .fold(*s as f64, |s, filter| filter.process(s)) as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8000;
+
+    // Noise decaying by 60 dB every `time` seconds, on top of steady noise at `floor` dB, both
+    // from the same generator so every run is the same.
+    fn decay(time: f32, seconds: f32, floor: f32) -> Vec<f32> {
+        let mut state = 1u32;
+        let mut noise = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+        };
+        let floor = 10f32.powf(floor / 20.0);
+        (0..(seconds * RATE as f32) as usize)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let level = 10f32.powf(-3.0 * t / time);
+                noise().signum() * level + noise() * floor
+            })
+            .collect()
+    }
+
+    fn assert_about(value: Option<f32>, expected: f32) {
+        let value = value.unwrap();
+        assert!(
+            (value - expected).abs() < expected * 0.05,
+            "{} is not about {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn noiseless_decay_is_used_whole() {
+        // Cut off while still decaying, so the end is no noise floor
+        let band = Band::analyse(None, &decay(1.0, 0.7, -200.0), RATE);
+        assert_about(band.edt, 1.0);
+        assert_about(band.t20, 1.0);
+        assert_about(band.t30, 1.0);
+    }
+
+    #[test]
+    fn decay_is_measured_above_the_noise() {
+        let band = Band::analyse(None, &decay(0.3, 1.5, -60.0), RATE);
+        assert_about(band.edt, 0.3);
+        assert_about(band.t20, 0.3);
+        assert_about(band.t30, 0.3);
+    }
+
+    #[test]
+    fn clarity_needs_energy_on_both_sides() {
+        let band = Band::analyse(None, &[1.0, 0.5], RATE);
+        assert_eq!(band.c50, None);
+        assert_eq!(band.d50, 1.0);
+
+        let band = Band::analyse(None, &decay(0.3, 1.5, -200.0), RATE);
+        assert!(band.c50.unwrap() < band.c80.unwrap());
+    }
+}
@@ -0,0 +1,408 @@
+use crate::dsp::Biquad;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// Loudness is measured in steps of 100 ms. The momentary window is four steps, the short-term
+// window thirty, and gating blocks for the integrated loudness start every step.
+const STEP: f64 = 0.1;
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+
+// Blocks quieter than this never count, in LUFS.
+const ABSOLUTE_GATE: f64 = -70.0;
+// Blocks this far below the loudness of the blocks above the absolute gate don't count, in LU.
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+
+// Gated levels are counted in bins this wide, in LU, from the absolute gate up to the ceiling in
+// LUFS. Anything louder is counted as the ceiling.
+const BIN_WIDTH: f64 = 0.1;
+const CEILING: f64 = 10.0;
+
+// True peaks are found by interpolating four points per sample, with a windowed sinc filter of
+// this many taps per point.
+const OVERSAMPLING: usize = 4;
+const TAPS: usize = 12;
+
+/// Loudness readings after ITU-R BS.1770 and EBU R 128. Levels that haven't been measured yet,
+/// or are silent, are negative infinity.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Loudness {
+    /// Over the last 400 ms, in LUFS.
+    pub momentary: f32,
+    /// Over the last 3 s, in LUFS.
+    pub short_term: f32,
+    /// Over everything measured, gated, in LUFS.
+    pub integrated: f32,
+    /// How much the short-term loudness varies (EBU Tech 3342), in LU.
+    pub range: f32,
+    /// The highest level between samples as well as at them, in dBTP.
+    pub true_peak: f32,
+}
+
+impl Default for Loudness {
+    fn default() -> Self {
+        Self {
+            momentary: f32::NEG_INFINITY,
+            short_term: f32::NEG_INFINITY,
+            integrated: f32::NEG_INFINITY,
+            range: 0.0,
+            true_peak: f32::NEG_INFINITY,
+        }
+    }
+}
+
+/// Measures the loudness of interleaved audio as it goes by.
+///
+/// Feeding a whole buffer through a new meter and reading it once is the offline measurement, so
+/// a meter that followed playback from start to end reads the same. Nothing is allocated after the
+/// meter is made, so it can measure on an audio thread: gated levels are kept in histograms to a
+/// tenth of an LU.
+#[derive(Debug, Clone)]
+pub struct Meter {
+    channels: usize,
+    // The K-weighting filters of each channel: a high shelf for the head, then a high-pass.
+    filters: Vec<[Biquad; 2]>,
+    weights: Vec<f64>,
+    // The weighted energy of the step being filled, the frames in it and the frames it needs.
+    step_energy: f64,
+    step_frames: usize,
+    step_len: usize,
+    // The mean energy of the latest steps, newest last.
+    recent: VecDeque<f64>,
+    // The mean energy of every gating block and short-term window so far, by level.
+    blocks: Histogram,
+    short_terms: Histogram,
+    // The latest samples of each channel, oldest first, for true peak interpolation.
+    history: Vec<[f32; TAPS]>,
+    interpolator: [[f32; TAPS]; OVERSAMPLING],
+    true_peak: f32,
+}
+
+impl Meter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let sample_rate = sample_rate.max(1);
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            filters: (0..channels).map(|_| k_weighting(sample_rate)).collect(),
+            weights: (0..channels).map(|c| weight(c, channels)).collect(),
+            step_energy: 0.0,
+            step_frames: 0,
+            step_len: ((STEP * sample_rate as f64) as usize).max(1),
+            recent: VecDeque::with_capacity(SHORT_TERM_STEPS),
+            blocks: Histogram::new(),
+            short_terms: Histogram::new(),
+            history: vec![[0.0; TAPS]; channels],
+            interpolator: interpolator(),
+            true_peak: 0.0,
+        }
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    /// Measures interleaved frames with as many channels as the meter.
+    pub fn process(&mut self, frames: &[f32]) {
+        for frame in frames.chunks_exact(self.channels) {
+            for (c, sample) in frame.iter().enumerate() {
+                let [shelf, high_pass] = &mut self.filters[c];
+                let weighted = high_pass.process(shelf.process(*sample as f64));
+                self.step_energy += self.weights[c] * weighted * weighted;
+
+                let history = &mut self.history[c];
+                history.copy_within(1.., 0);
+                history[TAPS - 1] = *sample;
+                for taps in self.interpolator.iter() {
+                    let point: f32 = taps.iter().zip(history.iter()).map(|(t, s)| t * s).sum();
+                    self.true_peak = self.true_peak.max(point.abs());
+                }
+            }
+
+            self.step_frames += 1;
+            if self.step_frames == self.step_len {
+                self.finish_step();
+            }
+        }
+    }
+
+    /// Forgets everything measured.
+    pub fn reset(&mut self) {
+        for filter in self.filters.iter_mut().flatten() {
+            filter.reset();
+        }
+        self.step_energy = 0.0;
+        self.step_frames = 0;
+        self.recent.clear();
+        self.blocks.clear();
+        self.short_terms.clear();
+        for history in self.history.iter_mut() {
+            *history = [0.0; TAPS];
+        }
+        self.true_peak = 0.0;
+    }
+
+    pub fn reading(&self) -> Loudness {
+        let window = |steps: usize| {
+            if self.recent.len() < steps {
+                return f32::NEG_INFINITY;
+            }
+            let energy = self.recent.iter().rev().take(steps).sum::<f64>() / steps as f64;
+            lufs(energy) as f32
+        };
+
+        Loudness {
+            momentary: window(MOMENTARY_STEPS),
+            short_term: window(SHORT_TERM_STEPS),
+            integrated: self.blocks.gate(INTEGRATED_RELATIVE_GATE).map_or(
+                f32::NEG_INFINITY,
+                |gate| {
+                    let (count, energy) = self.blocks.sum(gate);
+                    if count > 0 {
+                        lufs(energy / count as f64) as f32
+                    } else {
+                        f32::NEG_INFINITY
+                    }
+                },
+            ),
+            range: self.range(),
+            true_peak: 20.0 * self.true_peak.log10(),
+        }
+    }
+
+    fn finish_step(&mut self) {
+        if self.recent.len() == SHORT_TERM_STEPS {
+            self.recent.pop_front();
+        }
+        self.recent
+            .push_back(self.step_energy / self.step_len as f64);
+        self.step_energy = 0.0;
+        self.step_frames = 0;
+
+        let recent = &self.recent;
+        let mean = |steps: usize| recent.iter().rev().take(steps).sum::<f64>() / steps as f64;
+        if recent.len() >= MOMENTARY_STEPS {
+            self.blocks.add(mean(MOMENTARY_STEPS));
+        }
+        if recent.len() == SHORT_TERM_STEPS {
+            self.short_terms.add(mean(SHORT_TERM_STEPS));
+        }
+    }
+
+    // The spread between the 10th and 95th percentiles of the gated short-term loudness.
+    fn range(&self) -> f32 {
+        let gate = match self.short_terms.gate(RANGE_RELATIVE_GATE) {
+            Some(gate) => gate,
+            None => return 0.0,
+        };
+        let (count, _) = self.short_terms.sum(gate);
+        if count == 0 {
+            return 0.0;
+        }
+        let percentile = |p: f64| {
+            self.short_terms
+                .nth(gate, ((count - 1) as f64 * p).round() as u64)
+        };
+        (percentile(0.95) - percentile(0.1)) as f32
+    }
+}
+
+// Counts energies above the absolute gate by their level, adding up the energy at each level, so
+// they can be gated and ranked without keeping every one.
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: Vec<u64>,
+    energies: Vec<f64>,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        let bins = ((CEILING - ABSOLUTE_GATE) / BIN_WIDTH).round() as usize;
+        Self {
+            counts: vec![0; bins],
+            energies: vec![0.0; bins],
+        }
+    }
+
+    fn add(&mut self, energy: f64) {
+        let level = lufs(energy);
+        if level > ABSOLUTE_GATE {
+            let bin = (((level - ABSOLUTE_GATE) / BIN_WIDTH) as usize).min(self.counts.len() - 1);
+            self.counts[bin] += 1;
+            self.energies[bin] += energy;
+        }
+    }
+
+    fn clear(&mut self) {
+        for count in self.counts.iter_mut() {
+            *count = 0;
+        }
+        for energy in self.energies.iter_mut() {
+            *energy = 0.0;
+        }
+    }
+
+    // The first bin at or above `relative` LU below the loudness of everything counted, if
+    // anything is.
+    fn gate(&self, relative: f64) -> Option<usize> {
+        let (count, energy) = self.sum(0);
+        if count == 0 {
+            return None;
+        }
+        let threshold = lufs(energy / count as f64) + relative;
+        Some(((threshold - ABSOLUTE_GATE) / BIN_WIDTH).ceil().max(0.0) as usize)
+    }
+
+    // How many energies were counted from bin `from` up, and their total.
+    fn sum(&self, from: usize) -> (u64, f64) {
+        let from = from.min(self.counts.len());
+        (
+            self.counts[from..].iter().sum(),
+            self.energies[from..].iter().sum(),
+        )
+    }
+
+    // The level of the `n`th energy counted from bin `from` up, quietest first, at the middle of
+    // its bin.
+    fn nth(&self, from: usize, n: u64) -> f64 {
+        let mut seen = 0;
+        let mut bin = self.counts.len() - 1;
+        for (i, count) in self.counts.iter().enumerate().skip(from) {
+            seen += count;
+            if seen > n {
+                bin = i;
+                break;
+            }
+        }
+        ABSOLUTE_GATE + (bin as f64 + 0.5) * BIN_WIDTH
+    }
+}
+
+/// The loudness of a whole buffer of interleaved samples.
+pub fn analyse(samples: &[f32], sample_rate: u32, channels: u16) -> Loudness {
+    let mut meter = Meter::new(sample_rate, channels);
+    meter.process(samples);
+    meter.reading()
+}
+
+fn lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+// How much a channel counts towards the loudness. In a 5.1 layout (L, R, C, LFE, Ls, Rs) the
+// LFE is left out and the surrounds count extra; otherwise every channel counts the same.
+fn weight(channel: usize, channels: usize) -> f64 {
+    if channels == 6 {
+        [1.0, 1.0, 1.0, 0.0, 1.41, 1.41][channel]
+    } else {
+        1.0
+    }
+}
+
+// The K-weighting filter of BS.1770, with its two stages recomputed for any sample rate.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+
+    let (frequency, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * frequency / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let shelf = Biquad::new(
+        [
+            vh + vb * k / q + k * k,
+            2.0 * (k * k - vh),
+            vh - vb * k / q + k * k,
+        ],
+        [
+            1.0 + k / q + k * k,
+            2.0 * (k * k - 1.0),
+            1.0 - k / q + k * k,
+        ],
+    );
+
+    let (frequency, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * frequency / rate).tan();
+    // Only the feedback coefficients are normalised in the recommendation
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        [a0, -2.0 * a0, a0],
+        [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k],
+    );
+
+    [shelf, high_pass]
+}
+
+// The taps that interpolate each of the points between the two samples in the middle of the
+// history, from a Hann-windowed sinc.
+fn interpolator() -> [[f32; TAPS]; OVERSAMPLING] {
+    let mut taps = [[0.0; TAPS]; OVERSAMPLING];
+    let centre = (TAPS / 2 - 1) as f64;
+    for (point, taps) in taps.iter_mut().enumerate() {
+        for (i, tap) in taps.iter_mut().enumerate() {
+            let x = centre + point as f64 / OVERSAMPLING as f64 - i as f64;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (PI * x).sin() / (PI * x)
+            };
+            let window = 0.5 + 0.5 * (PI * x / (TAPS / 2) as f64).cos();
+            *tap = (sinc * window) as f32;
+        }
+    }
+    taps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    // A 997 Hz sine at `amplitude`, `seconds` long.
+    fn sine(amplitude: f32, seconds: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|i| {
+                amplitude * (2.0 * std::f32::consts::PI * 997.0 * i as f32 / RATE as f32).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sine_reads_at_its_level() {
+        // A full scale sine in one channel is -3.01 LUFS
+        let reading = analyse(&sine(0.1, 5.0), RATE, 1);
+        for level in [reading.momentary, reading.short_term, reading.integrated].iter() {
+            assert!((level + 23.01).abs() < 0.1, "{}", level);
+        }
+        assert!(reading.range < 0.2);
+        assert!((reading.true_peak + 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn range_spans_the_short_term_levels() {
+        let mut samples = sine(0.1, 10.0);
+        samples.extend(sine(0.1 / 10f32.sqrt(), 10.0));
+        let reading = analyse(&samples, RATE, 1);
+        assert!((reading.range - 10.0).abs() < 0.3, "{}", reading.range);
+
+        // Blocks ten LU down still count towards the integrated loudness
+        let energy = (1.0 + 0.1) / 2.0;
+        let expected = -23.01 + 10.0 * f32::log10(energy);
+        assert!(
+            (reading.integrated - expected).abs() < 0.2,
+            "{}",
+            reading.integrated
+        );
+    }
+
+    #[test]
+    fn reset_meter_reads_as_new() {
+        let mut meter = Meter::new(RATE, 1);
+        meter.process(&sine(0.5, 4.0));
+        meter.reset();
+        meter.process(&sine(0.1, 4.0));
+        assert_eq!(meter.reading(), analyse(&sine(0.1, 4.0), RATE, 1));
+    }
+}
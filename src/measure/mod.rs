@@ -0,0 +1,120 @@
+//! Acoustic measurement.
+
+pub mod acoustics;
+pub mod loudness;
+pub mod sweep;
+
+pub use acoustics::Acoustics;
+pub use loudness::{Loudness, Meter};
+pub use sweep::Sweep;
+
+use crate::decode;
+use crate::dsp;
+use crate::export::{self, BitDepth};
+use crate::Result;
+use std::path::Path;
+
+/// Stands in for playing `sweep` and recording it back, so a measurement can be checked offline.
+/// The sweep is convolved with `system`, or passed through untouched without one, then written to
+/// a 32-bit float WAV file at `path` and read back from it as the recorded response.
+pub fn loopback<P: AsRef<Path>>(
+    path: P,
+    sweep: &Sweep,
+    system: Option<&[f32]>,
+) -> Result<Vec<f32>> {
+    let path = path.as_ref();
+    let samples = sweep.generate();
+    let response = match system {
+        Some(system) => {
+            let mut response = dsp::convolve(&samples, system);
+            response.truncate(samples.len());
+            response
+        }
+        None => samples,
+    };
+
+    export::write_wav(
+        path,
+        &response,
+        export::Settings {
+            bit_depth: BitDepth::Float32,
+            sample_rate: sweep.sample_rate,
+            channels: 1,
+        },
+    )?;
+    Ok(decode::wav::open(path)?.samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sweep() -> Sweep {
+        Sweep {
+            duration: 1.0,
+            silence: 0.25,
+            sample_rate: 8000,
+            ..Sweep::default()
+        }
+    }
+
+    // A file in the temporary directory to stand in for the recording, removed when dropped.
+    struct Recording(PathBuf);
+
+    impl Recording {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "impulse-editor-{}-{}.wav",
+                std::process::id(),
+                name
+            )))
+        }
+    }
+
+    impl Drop for Recording {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn loopback_without_a_system_records_the_sweep() {
+        let recording = Recording::new("direct");
+        let sweep = sweep();
+        let response = loopback(&recording.0, &sweep, None).unwrap();
+        assert_eq!(response, sweep.generate());
+    }
+
+    #[test]
+    fn loopback_records_the_sweep_through_the_system() {
+        let recording = Recording::new("system");
+        let sweep = sweep();
+        let system = [0.0, 0.5, 0.25, 0.0, -0.125];
+        let response = loopback(&recording.0, &sweep, Some(&system)).unwrap();
+
+        let generated = sweep.generate();
+        assert_eq!(response.len(), generated.len());
+        for (i, sample) in response.iter().enumerate() {
+            let expected: f32 = system
+                .iter()
+                .enumerate()
+                .filter(|(delay, _)| *delay <= i)
+                .map(|(delay, gain)| generated[i - delay] * gain)
+                .sum();
+            assert!(
+                (sample - expected).abs() < 1e-4,
+                "{} != {}",
+                sample,
+                expected
+            );
+        }
+
+        // What the measurement makes of it is the system again
+        let impulse = sweep.deconvolve(&response);
+        let peak = (0..impulse.len())
+            .max_by(|&a, &b| impulse[a].abs().total_cmp(&impulse[b].abs()))
+            .unwrap();
+        assert_eq!(peak, 1);
+    }
+}
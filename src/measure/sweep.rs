@@ -0,0 +1,209 @@
+use crate::dsp;
+use std::f64::consts::PI;
+
+/// An exponential (logarithmic) sine sweep, for measuring impulse responses.
+///
+/// Playing the sweep through a system and deconvolving the recording with the sweep's inverse
+/// filter gives the system's impulse response. Because the sweep spends equal time on every
+/// octave, the harmonic distortion of the system lands before the linear impulse response instead
+/// of on top of it, and is cut away.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    pub start_frequency: f32,
+    /// Kept below the Nyquist frequency when the sweep is generated.
+    pub end_frequency: f32,
+    /// The length of the sweep itself, in seconds.
+    pub duration: f32,
+    /// Silence after the sweep, in seconds, for the system to ring out into while recording.
+    pub silence: f32,
+    /// The sweep's peak level, in dBFS.
+    pub gain_db: f32,
+    pub sample_rate: u32,
+}
+
+impl Sweep {
+    pub const MIN_DURATION: f32 = 1.0;
+    pub const MAX_DURATION: f32 = 60.0;
+    pub const MIN_GAIN_DB: f32 = -40.0;
+    pub const MAX_GAIN_DB: f32 = 0.0;
+
+    // The fade at each end that keeps the sweep from clicking, in seconds.
+    const FADE: f64 = 0.02;
+
+    /// The sweep followed by its silence.
+    pub fn generate(&self) -> Vec<f32> {
+        let mut samples = self.sweep();
+        let silence = (self.silence.max(0.0) * self.sample_rate as f32) as usize;
+        samples.resize(samples.len() + silence, 0.0);
+        samples
+    }
+
+    /// Recovers the impulse response of the system that `response` was recorded through, at the
+    /// sweep's sample rate. The impulse response starts when the sweep started in the recording
+    /// and is as long as the recording.
+    pub fn deconvolve(&self, response: &[f32]) -> Vec<f32> {
+        let inverse = self.inverse_filter();
+        if response.is_empty() || inverse.is_empty() {
+            return vec![];
+        }
+
+        // The linear response comes in as the inverse filter has passed all the way over the
+        // sweep; everything before it is distortion
+        let mut impulse = dsp::convolve(response, &inverse);
+        impulse.drain(..inverse.len() - 1);
+        impulse.truncate(response.len());
+        impulse
+    }
+
+    /// The filter that turns the sweep back into an impulse: the sweep reversed in time, falling
+    /// by 6 dB per octave to undo the sweep's extra energy in the low end. It is scaled so that a
+    /// sweep deconvolved with it is an impulse of unity gain.
+    pub fn inverse_filter(&self) -> Vec<f32> {
+        let sweep = self.sweep();
+        let rate = self.sample_rate as f64;
+        let rate_constant = self.rate_constant();
+
+        let mut inverse: Vec<f32> = sweep
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, s)| s * (-(i as f64) / rate / rate_constant).exp() as f32)
+            .collect();
+
+        // The sweep and its inverse are flat together, so their gain at any one frequency in the
+        // sweep is their gain at all of them
+        let (start, end) = self.frequencies();
+        let omega = 2.0 * PI * (start * end).sqrt() / rate;
+        let gain = spectrum_at(&sweep, omega) * spectrum_at(&inverse, omega);
+        if gain > 0.0 {
+            let scale = gain.recip() as f32;
+            for sample in inverse.iter_mut() {
+                *sample *= scale;
+            }
+        }
+        inverse
+    }
+
+    // The sweep on its own, faded in and out.
+    fn sweep(&self) -> Vec<f32> {
+        let rate = self.sample_rate.max(1) as f64;
+        let len = (self.duration.max(0.0) as f64 * rate) as usize;
+        let (start, _) = self.frequencies();
+        let rate_constant = self.rate_constant();
+        let amplitude = self.amplitude();
+        let fade = ((Self::FADE * rate) as usize).min(len / 2).max(1);
+
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / rate;
+                let phase = 2.0 * PI * start * rate_constant * ((t / rate_constant).exp() - 1.0);
+                let edge = i.min(len - 1 - i);
+                let envelope = if edge < fade {
+                    0.5 - 0.5 * (PI * edge as f64 / fade as f64).cos()
+                } else {
+                    1.0
+                };
+                (amplitude * envelope * phase.sin()) as f32
+            })
+            .collect()
+    }
+
+    fn frequencies(&self) -> (f64, f64) {
+        let nyquist = self.sample_rate as f64 / 2.0;
+        let start = (self.start_frequency as f64).clamp(1.0, nyquist * 0.5);
+        let end = (self.end_frequency as f64).clamp(start * 2.0, nyquist * 0.95);
+        (start, end)
+    }
+
+    // How long the sweep takes to rise by a factor of e in frequency, in seconds.
+    fn rate_constant(&self) -> f64 {
+        let (start, end) = self.frequencies();
+        self.duration.max(f32::EPSILON) as f64 / (end / start).ln()
+    }
+
+    fn amplitude(&self) -> f64 {
+        10f64.powf(self.gain_db as f64 / 20.0)
+    }
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            start_frequency: 20.0,
+            end_frequency: 20000.0,
+            duration: 10.0,
+            silence: 3.0,
+            gain_db: -6.0,
+            sample_rate: 48000,
+        }
+    }
+}
+
+// The magnitude of the discrete-time Fourier transform of `samples` at `omega` radians per sample.
+fn spectrum_at(samples: &[f32], omega: f64) -> f64 {
+    let (re, im) = samples
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (i, s)| {
+            let angle = omega * i as f64;
+            (re + *s as f64 * angle.cos(), im - *s as f64 * angle.sin())
+        });
+    re.hypot(im)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep() -> Sweep {
+        Sweep {
+            duration: 1.0,
+            silence: 0.25,
+            gain_db: 0.0,
+            sample_rate: 8000,
+            ..Sweep::default()
+        }
+    }
+
+    // The gain of `impulse` at frequencies across the sweep, which is all it can measure. A
+    // sweep this short loses a little to what is cut away with the distortion.
+    fn gains(impulse: &[f32]) -> Vec<f64> {
+        [100.0, 300.0, 1000.0, 3000.0]
+            .iter()
+            .map(|frequency| spectrum_at(impulse, 2.0 * PI * frequency / 8000.0))
+            .collect()
+    }
+
+    fn peak(impulse: &[f32]) -> usize {
+        (0..impulse.len())
+            .max_by(|&a, &b| impulse[a].abs().total_cmp(&impulse[b].abs()))
+            .unwrap()
+    }
+
+    #[test]
+    fn deconvolving_the_sweep_gives_a_unit_impulse() {
+        let sweep = sweep();
+        let impulse = sweep.deconvolve(&sweep.generate());
+        assert_eq!(impulse.len(), sweep.generate().len());
+        assert_eq!(peak(&impulse), 0);
+        for gain in gains(&impulse) {
+            assert!((gain - 1.0).abs() < 0.1, "gain {}", gain);
+        }
+        // Band-limited, so it rings, but not for long
+        assert!(impulse[50..].iter().all(|s| s.abs() < 0.02));
+    }
+
+    #[test]
+    fn deconvolving_a_response_gives_the_system() {
+        let sweep = sweep();
+        let system = [0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -0.25];
+        let mut response = dsp::convolve(&sweep.generate(), &system);
+        response.truncate(sweep.generate().len());
+
+        let impulse = sweep.deconvolve(&response);
+        assert_eq!(peak(&impulse), 3);
+        for (gain, expected) in gains(&impulse).iter().zip(gains(&system)) {
+            assert!((gain - expected).abs() < 0.1, "{} != {}", gain, expected);
+        }
+    }
+}
@@ -0,0 +1,713 @@
+use crate::decode::AudioInfo;
+use crate::effect::Chain;
+use crate::measure::{Loudness, Meter};
+use dasp::Sample;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::FRAC_PI_4;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The loudness of each source, in the order they were set, and of the output.
+pub type Readings = (Vec<Loudness>, Loudness);
+
+/// A track to be mixed: a shared buffer of interleaved samples, the format they are in and how
+/// loud and where they are mixed.
+#[derive(Clone)]
+pub struct Source<T> {
+    pub samples: Arc<Mutex<Vec<T>>>,
+    pub info: AudioInfo,
+    pub strip: Strip,
+    /// Applied to mono sources only, after resampling to the output rate. A chain that widens
+    /// turns its source into one with as many channels.
+    pub effects: Arc<Mutex<Chain>>,
+}
+
+/// A track's mixer settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Strip {
+    pub gain_db: f32,
+    /// From -1 (hard left) to 1 (hard right).
+    pub pan: f32,
+    pub pan_law: PanLaw,
+    pub mute: bool,
+    /// While any track is soloed, only soloed tracks are heard.
+    pub solo: bool,
+}
+
+impl Strip {
+    pub const MIN_GAIN_DB: f32 = -60.0;
+    pub const MAX_GAIN_DB: f32 = 12.0;
+
+    /// The linear gain. The lowest setting is treated as silence.
+    pub fn gain(&self) -> f32 {
+        if self.gain_db <= Self::MIN_GAIN_DB {
+            0.0
+        } else {
+            10f32.powf(self.gain_db / 20.0)
+        }
+    }
+}
+
+impl Default for Strip {
+    fn default() -> Self {
+        Self {
+            gain_db: 0.0,
+            pan: 0.0,
+            pan_law: PanLaw::default(),
+            mute: false,
+            solo: false,
+        }
+    }
+}
+
+/// How panning a mono track splits it between the left and right outputs, named after how much
+/// each side is attenuated with the track panned to the centre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PanLaw {
+    /// 0 dB: the far side is turned down and the near side left alone.
+    Balance,
+    /// -3 dB: the total power stays the same wherever the track is.
+    #[default]
+    ConstantPower,
+    /// -4.5 dB: halfway between constant power and linear.
+    Compromise,
+    /// -6 dB: the sides sum to the same amplitude wherever the track is.
+    Linear,
+}
+
+impl PanLaw {
+    pub const ALL: [PanLaw; 4] = [
+        PanLaw::Balance,
+        PanLaw::ConstantPower,
+        PanLaw::Compromise,
+        PanLaw::Linear,
+    ];
+
+    /// The left and right gains for `pan`, from -1 (hard left) to 1 (hard right).
+    pub fn gains(&self, pan: f32) -> (f32, f32) {
+        let pan = pan.clamp(-1.0, 1.0);
+        let linear = ((1.0 - pan) / 2.0, (1.0 + pan) / 2.0);
+        let angle = (pan + 1.0) * FRAC_PI_4;
+        let power = (angle.cos(), angle.sin());
+        match self {
+            PanLaw::Balance => ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0)),
+            PanLaw::ConstantPower => power,
+            PanLaw::Compromise => ((linear.0 * power.0).sqrt(), (linear.1 * power.1).sqrt()),
+            PanLaw::Linear => linear,
+        }
+    }
+}
+
+impl fmt::Display for PanLaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PanLaw::Balance => "0 dB (balance)",
+                PanLaw::ConstantPower => "-3 dB (constant power)",
+                PanLaw::Compromise => "-4.5 dB",
+                PanLaw::Linear => "-6 dB (linear)",
+            }
+        )
+    }
+}
+
+/// Mixes every source down to one interleaved output, resampling each to the output rate.
+///
+/// The play position is kept in output frames and shared through atomics, so it can be read from
+/// the UI while an output stream renders on its own thread. Loudness readings are shared the same
+/// way, published after every render unless the UI is reading them at that moment.
+pub struct Mixer<T> {
+    sources: Vec<Source<T>>,
+    sample_rate: u32,
+    channels: u16,
+    position: Arc<AtomicU64>,
+    playing: Arc<AtomicBool>,
+    // Where the last render ended, to tell when playback has jumped.
+    next: u64,
+    // The length of each source in output frames, as of the last time its buffer was free.
+    lengths: Vec<u64>,
+    // One source's frames, resampled to the output rate, and the same frames widened by its
+    // effects.
+    block: Vec<f32>,
+    widened: Vec<f32>,
+    // The loudness of each source, after its effects and gain, and of the output.
+    meters: Vec<Meter>,
+    master: Meter,
+    readings: Arc<Mutex<Readings>>,
+}
+
+impl<T> Mixer<T>
+where
+    T: Sample,
+{
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sources: vec![],
+            sample_rate,
+            channels,
+            position: Arc::new(AtomicU64::new(0)),
+            playing: Arc::new(AtomicBool::new(false)),
+            // Nothing has been rendered, so the first render is a jump
+            next: u64::MAX,
+            lengths: vec![],
+            block: vec![],
+            widened: vec![],
+            meters: vec![],
+            master: Meter::new(sample_rate, channels),
+            readings: Arc::new(Mutex::new((vec![], Loudness::default()))),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Replaces the sources being mixed. A source reading the same samples as one being mixed
+    /// already keeps its meter, so changing a track's settings, or adding and removing others,
+    /// doesn't restart its measurement.
+    pub fn set_sources(&mut self, sources: Vec<Source<T>>) {
+        let mut meters: Vec<Option<Meter>> = std::mem::take(&mut self.meters)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.meters = sources
+            .iter()
+            .map(|source| {
+                let channels = output_channels(source.info, &source.effects.lock().unwrap()) as u16;
+                let kept = self
+                    .sources
+                    .iter()
+                    .position(|old| Arc::ptr_eq(&old.samples, &source.samples))
+                    .and_then(|i| meters[i].take());
+                match kept {
+                    Some(meter) if meter.channels() == channels => meter,
+                    _ => Meter::new(self.sample_rate, channels),
+                }
+            })
+            .collect();
+        *self.readings.lock().unwrap() = self.loudness();
+        self.lengths = sources
+            .iter()
+            .map(|source| {
+                playing_frames(
+                    source.info,
+                    source.samples.lock().unwrap().len(),
+                    &source.effects.lock().unwrap(),
+                    self.sample_rate,
+                )
+            })
+            .collect();
+        // Blocks primed after a jump are as long as the latency, often longer than what a stream
+        // asks for, so they are made room for here rather than while rendering
+        let primed = sources
+            .iter()
+            .map(|source| {
+                let effects = source.effects.lock().unwrap();
+                effects.latency() * effects.channels()
+            })
+            .max()
+            .unwrap_or(0);
+        self.block.clear();
+        self.block.reserve(primed);
+        self.widened.clear();
+        self.widened.reserve(primed);
+        self.sources = sources;
+    }
+
+    /// The loudness of each source, in the order they were set, and of the output, measured
+    /// since playback last jumped.
+    pub fn loudness(&self) -> Readings {
+        (
+            self.meters.iter().map(Meter::reading).collect(),
+            self.master.reading(),
+        )
+    }
+
+    /// The readings of `loudness` as of the last render, which can be read without the mixer.
+    pub fn readings(&self) -> Arc<Mutex<Readings>> {
+        self.readings.clone()
+    }
+
+    pub fn position(&self) -> Arc<AtomicU64> {
+        self.position.clone()
+    }
+
+    pub fn playing(&self) -> Arc<AtomicBool> {
+        self.playing.clone()
+    }
+
+    /// The number of output frames until the longest source ends, including the tails of its
+    /// effects.
+    pub fn len(&self) -> u64 {
+        self.sources
+            .iter()
+            .map(|source| {
+                playing_frames(
+                    source.info,
+                    source.samples.lock().unwrap().len(),
+                    &source.effects.lock().unwrap(),
+                    self.sample_rate,
+                )
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Renders every source from the start to the end of the longest one, exactly as playback
+    /// would, leaving the play position untouched.
+    ///
+    /// Each source's effects are swapped for a copy first, prepared for this mixer and reset, so
+    /// the render starts from silence and never disturbs a chain that is also playing.
+    pub fn render_all(&mut self) -> Vec<f32> {
+        for source in self.sources.iter_mut() {
+            let mut effects = source.effects.lock().unwrap().clone();
+            effects.prepare(self.sample_rate);
+            effects.reset();
+            source.effects = Arc::new(Mutex::new(effects));
+        }
+        self.next = u64::MAX;
+
+        let position = self.position.load(Ordering::Relaxed);
+        let playing = self.playing.load(Ordering::Relaxed);
+
+        let mut out = vec![0.0; self.len() as usize * self.channels.max(1) as usize];
+        self.position.store(0, Ordering::Relaxed);
+        self.playing.store(true, Ordering::Relaxed);
+        self.render(&mut out);
+
+        self.position.store(position, Ordering::Relaxed);
+        self.playing.store(playing, Ordering::Relaxed);
+        out
+    }
+
+    /// Fills `out` with the next interleaved frames while playing, or with silence while paused.
+    /// Playback stops by itself once every source has ended.
+    ///
+    /// This never waits on a lock, so it can run on an audio thread: a source whose samples or
+    /// effects are in use elsewhere is left out of the block, and keeps the length it last had.
+    /// Effects must already be prepared for the output rate, as `Engine::set_sources` does, since
+    /// preparing may allocate.
+    pub fn render(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = 0.0;
+        }
+        if !self.playing.load(Ordering::Relaxed) {
+            return;
+        }
+
+        for (source, length) in self.sources.iter().zip(self.lengths.iter_mut()) {
+            if let (Ok(samples), Ok(effects)) =
+                (source.samples.try_lock(), source.effects.try_lock())
+            {
+                *length = playing_frames(source.info, samples.len(), &effects, self.sample_rate);
+            }
+        }
+        let len = self.lengths.iter().copied().max().unwrap_or(0);
+        let channels = self.channels.max(1) as usize;
+        let start = self.position.load(Ordering::Relaxed);
+        let frames = (len.saturating_sub(start) as usize).min(out.len() / channels);
+        // Effects hold on to audio from earlier blocks, and meters measure from where playback
+        // started, neither of which follows on after a jump
+        let continuous = start == self.next;
+        if !continuous {
+            self.master.reset();
+        }
+
+        // Muted tracks are skipped, as is every unsoloed track while something is soloed
+        let soloing = self.sources.iter().any(|source| source.strip.solo);
+        for (source, meter) in self.sources.iter().zip(self.meters.iter_mut()) {
+            let audible = !source.strip.mute && (source.strip.solo || !soloing);
+            if !audible {
+                meter.reset();
+                continue;
+            }
+            if !continuous {
+                meter.reset();
+            }
+
+            let (samples, mut effects) =
+                match (source.samples.try_lock(), source.effects.try_lock()) {
+                    (Ok(samples), Ok(effects)) => (samples, effects),
+                    _ => continue,
+                };
+            let source_channels = source.info.channels.max(1) as usize;
+            let processed = source_channels == 1 && !effects.is_empty();
+            let block_channels = output_channels(source.info, &effects);
+
+            // Processed sources are read ahead by the effects' latency, so they stay in time
+            let mut offset = 0;
+            if processed {
+                debug_assert!(effects.is_prepared(self.sample_rate));
+                offset = effects.latency() as u64;
+                if !continuous {
+                    // Primed with what comes before the read-ahead, which would be lost otherwise
+                    effects.reset();
+                    self.block.clear();
+                    self.block.resize(offset as usize, 0.0);
+                    for (i, frame) in self.block.chunks_mut(1).enumerate() {
+                        read_frame(
+                            frame,
+                            &samples,
+                            source.info,
+                            start + i as u64,
+                            self.sample_rate,
+                        );
+                    }
+                    process(&mut effects, &mut self.block, &mut self.widened);
+                }
+            }
+
+            self.block.clear();
+            self.block.resize(frames * source_channels, 0.0);
+            for (i, frame) in self.block.chunks_mut(source_channels).enumerate() {
+                read_frame(
+                    frame,
+                    &samples,
+                    source.info,
+                    start + offset + i as u64,
+                    self.sample_rate,
+                );
+            }
+            if processed {
+                process(&mut effects, &mut self.block, &mut self.widened);
+            }
+            let gain = source.strip.gain();
+            for sample in self.block.iter_mut() {
+                *sample *= gain;
+            }
+            // Effects added since the sources were set may have widened the source
+            if meter.channels() as usize == block_channels {
+                meter.process(&self.block);
+            }
+
+            for (out, frame) in out
+                .chunks_mut(channels)
+                .zip(self.block.chunks(block_channels))
+            {
+                mix_frame(out, frame, &source.strip);
+            }
+        }
+        self.master.process(&out[..frames * channels]);
+        if let Ok(mut readings) = self.readings.try_lock() {
+            let (sources, master) = &mut *readings;
+            for (reading, meter) in sources.iter_mut().zip(self.meters.iter()) {
+                *reading = meter.reading();
+            }
+            *master = self.master.reading();
+        }
+
+        if frames < out.len() / channels {
+            self.playing.store(false, Ordering::Relaxed);
+        }
+
+        // A seek from another thread while rendering takes precedence over advancing
+        let position = start + frames as u64;
+        self.next = position;
+        let _ =
+            self.position
+                .compare_exchange(start, position, Ordering::Relaxed, Ordering::Relaxed);
+    }
+}
+
+// How many channels a source has once through its effects.
+fn output_channels(info: AudioInfo, effects: &Chain) -> usize {
+    match info.channels.max(1) {
+        1 if !effects.is_empty() => effects.channels(),
+        channels => channels as usize,
+    }
+}
+
+// Runs a mono block through a chain, leaving its output in `block`, which is widened with the
+// help of `widened` when the chain widens.
+fn process(effects: &mut Chain, block: &mut Vec<f32>, widened: &mut Vec<f32>) {
+    let channels = effects.channels();
+    if channels == 1 {
+        effects.process(block);
+    } else {
+        widened.clear();
+        widened.resize(block.len() * channels, 0.0);
+        effects.process_wide(block, widened);
+        std::mem::swap(block, widened);
+    }
+}
+
+// How many output frames at `sample_rate` a buffer of `samples` samples in `info`'s format lasts.
+fn output_frames(info: AudioInfo, samples: usize, sample_rate: u32) -> u64 {
+    let frames = samples / info.channels.max(1) as usize;
+    (frames as u64 * sample_rate as u64).div_ceil(info.sample_rate.max(1) as u64)
+}
+
+// How many output frames a source plays for, including the tail its effects add after its
+// samples end. Only mono sources go through their effects.
+fn playing_frames(info: AudioInfo, samples: usize, effects: &Chain, sample_rate: u32) -> u64 {
+    let tail = if info.channels.max(1) == 1 {
+        effects.tail() as u64
+    } else {
+        0
+    };
+    output_frames(info, samples, sample_rate) + tail
+}
+
+// Reads the source frame playing at output frame `position` into `frame`, linearly interpolating
+// between source frames when the rates differ. Past the end of the source the frame is silent.
+fn read_frame<T: Sample>(
+    frame: &mut [f32],
+    samples: &[T],
+    info: AudioInfo,
+    position: u64,
+    sample_rate: u32,
+) {
+    let source_channels = frame.len();
+    let frames = samples.len() / source_channels;
+    let time = position as f64 * info.sample_rate as f64 / sample_rate as f64;
+    let index = time as usize;
+    if index >= frames {
+        return;
+    }
+    let fraction = (time - index as f64) as f32;
+
+    for (channel, out) in frame.iter_mut().enumerate() {
+        let a = samples[index * source_channels + channel]
+            .to_float_sample()
+            .to_sample::<f32>();
+        *out = if index + 1 < frames {
+            let b = samples[(index + 1) * source_channels + channel]
+                .to_float_sample()
+                .to_sample::<f32>();
+            a + (b - a) * fraction
+        } else {
+            a
+        };
+    }
+}
+
+// Adds a source frame, with its gain already applied, onto an output frame through the source's
+// pan. Mono sources are panned between the first two channels and sent unpanned to any others,
+// multichannel sources are balanced between left and right, and everything is averaged down for a
+// mono output.
+fn mix_frame(out: &mut [f32], frame: &[f32], strip: &Strip) {
+    if out.len() == 1 {
+        out[0] += frame.iter().sum::<f32>() / frame.len() as f32;
+        return;
+    }
+    let (left, right) = if frame.len() == 1 {
+        strip.pan_law.gains(strip.pan)
+    } else {
+        PanLaw::Balance.gains(strip.pan)
+    };
+    for (channel, sample) in out.iter_mut().enumerate() {
+        let input = if frame.len() == 1 {
+            frame[0]
+        } else {
+            frame.get(channel).copied().unwrap_or(0.0)
+        };
+        *sample += input
+            * match channel {
+                0 => left,
+                1 => right,
+                _ => 1.0,
+            };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::{Convolution, Effect, Parameter};
+
+    const PARAMETERS: [Parameter; 1] = [Parameter {
+        name: "Held",
+        unit: "",
+        min: -1.0,
+        max: 1.0,
+        default: 0.0,
+        step: 0.01,
+    }];
+
+    // Delays its input by a sample, and shows the sample it holds as its parameter. The delay is
+    // reported either as latency, to be compensated, or as a tail.
+    #[derive(Clone, Default)]
+    struct Hold(f32, bool);
+
+    impl Effect for Hold {
+        fn name(&self) -> &'static str {
+            "Hold"
+        }
+
+        fn parameters(&self) -> &[Parameter] {
+            &PARAMETERS
+        }
+
+        fn parameter(&self, _index: usize) -> f32 {
+            self.0
+        }
+
+        fn set_parameter(&mut self, _index: usize, value: f32) {
+            self.0 = value;
+        }
+
+        fn prepare(&mut self, _sample_rate: u32) {}
+
+        fn process(&mut self, block: &mut [f32]) {
+            for sample in block.iter_mut() {
+                *sample = std::mem::replace(&mut self.0, *sample);
+            }
+        }
+
+        fn latency(&self) -> usize {
+            self.1 as usize
+        }
+
+        fn tail(&self) -> usize {
+            !self.1 as usize
+        }
+
+        fn reset(&mut self) {
+            self.0 = 0.0;
+        }
+
+        fn duplicate(&self) -> Box<dyn Effect> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn source(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Source<f32> {
+        Source {
+            samples: Arc::new(Mutex::new(samples)),
+            info: AudioInfo {
+                sample_rate,
+                channels,
+                ..AudioInfo::default()
+            },
+            strip: Strip::default(),
+            effects: Arc::new(Mutex::new(Chain::new())),
+        }
+    }
+
+    #[test]
+    fn pan_laws_attenuate_the_centre() {
+        let centre = |law: PanLaw| {
+            let (left, right) = law.gains(0.0);
+            assert!((left - right).abs() < 1e-6);
+            20.0 * left.log10()
+        };
+        assert!(centre(PanLaw::Balance).abs() < 0.01);
+        assert!((centre(PanLaw::ConstantPower) + 3.01).abs() < 0.01);
+        assert!((centre(PanLaw::Compromise) + 4.52).abs() < 0.01);
+        assert!((centre(PanLaw::Linear) + 6.02).abs() < 0.01);
+
+        for law in PanLaw::ALL.iter() {
+            let (left, right) = law.gains(-1.0);
+            assert!((left - 1.0).abs() < 1e-6 && right.abs() < 1e-6, "{:?}", law);
+            assert_eq!(law.gains(2.0), law.gains(1.0));
+        }
+        assert_eq!(PanLaw::Balance.gains(0.5), (0.5, 1.0));
+    }
+
+    #[test]
+    fn mono_sources_pan_across_the_front_of_wide_outputs() {
+        let mut left = source(vec![0.5], 100, 1);
+        left.strip.pan = -1.0;
+        let mut mixer = Mixer::new(100, 4);
+        mixer.set_sources(vec![left]);
+        assert_eq!(mixer.render_all(), vec![0.5, 0.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn offline_render_leaves_playing_effects_alone() {
+        let held = source(vec![0.1, 0.2, 0.3], 100, 1);
+        held.effects.lock().unwrap().push(Box::new(Hold::default()));
+        held.effects.lock().unwrap().prepare(100);
+
+        let mut playing = Mixer::new(100, 1);
+        playing.set_sources(vec![held.clone()]);
+        playing.playing().store(true, Ordering::Relaxed);
+        let mut out = [0.0; 2];
+        playing.render(&mut out);
+        assert_eq!(out, [0.0, 0.1]);
+
+        let mut offline = Mixer::new(100, 1);
+        offline.set_sources(vec![held.clone()]);
+        assert_eq!(offline.render_all(), vec![0.0, 0.1, 0.2, 0.3]);
+        assert_eq!(
+            held.effects.lock().unwrap().get(0).unwrap().parameter(0),
+            0.2
+        );
+
+        playing.render(&mut out);
+        assert_eq!(out[0], 0.2);
+    }
+
+    #[test]
+    fn latency_is_compensated_after_a_jump() {
+        let held = source(vec![0.1, 0.2, 0.3, 0.4], 100, 1);
+        held.effects.lock().unwrap().push(Box::new(Hold(0.0, true)));
+        let mut mixer = Mixer::new(100, 1);
+        mixer.set_sources(vec![held]);
+        assert_eq!(mixer.len(), 4);
+        assert_eq!(mixer.render_all(), vec![0.1, 0.2, 0.3, 0.4]);
+
+        mixer.position().store(2, Ordering::Relaxed);
+        mixer.playing().store(true, Ordering::Relaxed);
+        let mut out = [0.0; 2];
+        mixer.render(&mut out);
+        assert_eq!(out, [0.3, 0.4]);
+    }
+
+    #[test]
+    fn stereo_reverb_widens_a_mono_source() {
+        let dry = source(vec![1.0], 100, 1);
+        let mut reverb = Convolution::with_channels(vec![vec![1.0], vec![0.0, 0.5]], 100);
+        reverb.set_parameter(0, 1.0);
+        dry.effects.lock().unwrap().push(Box::new(reverb));
+
+        let mut mixer = Mixer::new(100, 2);
+        mixer.set_sources(vec![dry]);
+        let out = mixer.render_all();
+        // Scaled to the average energy of the two channels
+        let scale = 0.625f32.sqrt().recip();
+        let expected = [scale, 0.0, 0.0, 0.5 * scale, 0.0, 0.0];
+        assert_eq!(out.len(), expected.len());
+        for (out, expected) in out.iter().zip(expected.iter()) {
+            assert!((out - expected).abs() < 1e-5, "{:?}", out);
+        }
+        // Metered as the stereo source it has become
+        assert_eq!(mixer.meters[0].channels(), 2);
+    }
+
+    #[test]
+    fn meters_follow_their_sources() {
+        let tone = |amplitude: f32| {
+            let samples = (0..8000)
+                .map(|i| amplitude * (i as f32 * 0.3).sin())
+                .collect();
+            source(samples, 8000, 1)
+        };
+        let (quiet, loud) = (tone(0.01), tone(0.5));
+        let mut mixer = Mixer::new(8000, 1);
+        mixer.set_sources(vec![quiet, loud.clone()]);
+        mixer.playing().store(true, Ordering::Relaxed);
+        let mut out = vec![0.0; 4000];
+        mixer.render(&mut out);
+
+        let readings = mixer.readings();
+        let (before, _) = readings.lock().unwrap().clone();
+        assert_eq!(before, mixer.loudness().0);
+        assert!(before[1].momentary > before[0].momentary + 20.0);
+
+        // Removing the first track leaves the second with its own meter
+        mixer.set_sources(vec![loud]);
+        assert_eq!(readings.lock().unwrap().0, vec![before[1]]);
+    }
+}
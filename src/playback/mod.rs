@@ -0,0 +1,348 @@
+pub mod mixer;
+pub mod record;
+
+pub use mixer::{Mixer, PanLaw, Readings, Source, Strip};
+pub use record::Recorder;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use dasp::Sample;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+pub enum Error {
+    NoOutputDevice,
+    NoInputDevice,
+    DefaultStreamConfig(cpal::DefaultStreamConfigError),
+    BuildStream(cpal::BuildStreamError),
+    PlayStream(cpal::PlayStreamError),
+    PauseStream(cpal::PauseStreamError),
+    /// The device went away while a stream was open on it.
+    Disconnected,
+    Stream(cpal::BackendSpecificError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoOutputDevice => write!(f, "no audio output device is available"),
+            Error::NoInputDevice => write!(f, "no audio input device is available"),
+            Error::DefaultStreamConfig(e) => write!(f, "{}", e),
+            Error::BuildStream(e) => write!(f, "{}", e),
+            Error::PlayStream(e) => write!(f, "{}", e),
+            Error::PauseStream(e) => write!(f, "{}", e),
+            Error::Disconnected => write!(f, "the audio device was disconnected"),
+            Error::Stream(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Passes the errors a stream runs into on its own thread on through `errors`.
+fn report(errors: Sender<Error>) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    move |e| {
+        let _ = errors.send(match e {
+            cpal::StreamError::DeviceNotAvailable => Error::Disconnected,
+            cpal::StreamError::BackendSpecific { err } => Error::Stream(err),
+        });
+    }
+}
+
+/// Plays a set of sources from a shared position, which is kept across pauses.
+///
+/// An engine either drives a cpal output stream, or is a null output that renders nothing on its
+/// own: audio is then pulled with `render`, which is how tests and offline rendering run.
+pub struct Engine<T> {
+    mixer: Arc<Mutex<Mixer<T>>>,
+    sample_rate: u32,
+    channels: u16,
+    position: Arc<AtomicU64>,
+    playing: Arc<AtomicBool>,
+    readings: Arc<Mutex<Readings>>,
+    stream: Option<cpal::Stream>,
+    // Errors from the stream, which has no other way to report them.
+    reporter: Sender<Error>,
+    errors: Receiver<Error>,
+}
+
+impl<T> Engine<T>
+where
+    T: Sample + Send + 'static,
+{
+    /// Opens the default output device of the default host.
+    pub fn new() -> Result<Self, Error> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(Error::NoOutputDevice)?;
+        let supported = device
+            .default_output_config()
+            .map_err(Error::DefaultStreamConfig)?;
+        let config: cpal::StreamConfig = supported.config();
+
+        let mut engine = Self::null(config.sample_rate.0, config.channels);
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => engine.build_stream::<f32>(&device, &config),
+            cpal::SampleFormat::I16 => engine.build_stream::<i16>(&device, &config),
+            cpal::SampleFormat::U16 => engine.build_stream::<u16>(&device, &config),
+        }?;
+        // Some hosts start streams as soon as they are built
+        stream.pause().map_err(Error::PauseStream)?;
+        engine.stream = Some(stream);
+
+        Ok(engine)
+    }
+
+    /// An engine with no device behind it, rendering `channels` channels at `sample_rate`.
+    pub fn null(sample_rate: u32, channels: u16) -> Self {
+        let mixer = Mixer::new(sample_rate, channels);
+        let (reporter, errors) = mpsc::channel();
+        Self {
+            sample_rate,
+            channels,
+            position: mixer.position(),
+            playing: mixer.playing(),
+            readings: mixer.readings(),
+            mixer: Arc::new(Mutex::new(mixer)),
+            stream: None,
+            reporter,
+            errors,
+        }
+    }
+
+    fn build_stream<S: cpal::Sample>(
+        &self,
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+    ) -> Result<cpal::Stream, Error> {
+        let mixer = self.mixer.clone();
+        let mut scratch = vec![];
+        device
+            .build_output_stream(
+                config,
+                move |data: &mut [S], _: &cpal::OutputCallbackInfo| {
+                    scratch.resize(data.len(), 0.0);
+                    // Waiting on the UI thread would be heard as a dropout anyway
+                    match mixer.try_lock() {
+                        Ok(mut mixer) => mixer.render(&mut scratch),
+                        Err(_) => scratch.iter_mut().for_each(|sample| *sample = 0.0),
+                    }
+                    for (out, sample) in data.iter_mut().zip(scratch.iter()) {
+                        *out = S::from(sample);
+                    }
+                },
+                report(self.reporter.clone()),
+            )
+            .map_err(Error::BuildStream)
+    }
+
+    /// The next error the output stream ran into, if there are any yet to be seen. After
+    /// `Error::Disconnected` nothing more will play, and a new engine has to be opened.
+    pub fn error(&self) -> Option<Error> {
+        self.errors.try_recv().ok()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Replaces the sources being mixed, keeping the current position. Their effects are prepared
+    /// for the output here, so the stream never has to.
+    pub fn set_sources(&self, sources: Vec<Source<T>>) {
+        // Before the mixer is locked, which would leave the stream silent meanwhile
+        for source in sources.iter() {
+            source.effects.lock().unwrap().prepare(self.sample_rate);
+        }
+        self.mixer.lock().unwrap().set_sources(sources);
+    }
+
+    /// Starts playing from the current position, or from the start if the end was reached.
+    pub fn play(&mut self) -> Result<(), Error> {
+        if self.position() >= self.mixer.lock().unwrap().len() {
+            self.seek(0);
+        }
+        self.playing.store(true, Ordering::Relaxed);
+        if let Some(stream) = &self.stream {
+            stream.play().map_err(Error::PlayStream)?;
+        }
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<(), Error> {
+        self.playing.store(false, Ordering::Relaxed);
+        if let Some(stream) = &self.stream {
+            stream.pause().map_err(Error::PauseStream)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Relaxed)
+    }
+
+    /// The play position, in output frames.
+    pub fn position(&self) -> u64 {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    pub fn seek(&self, position: u64) {
+        self.position.store(position, Ordering::Relaxed);
+    }
+
+    /// The loudness of each source and of the output, as `Mixer::loudness` read at the end of
+    /// the last block played.
+    pub fn loudness(&self) -> Readings {
+        self.readings.lock().unwrap().clone()
+    }
+
+    /// Pulls the next interleaved frames from a null engine. Engines with a device render from
+    /// their stream instead, so this should only be used on engines made with `null`.
+    pub fn render(&self, out: &mut [f32]) {
+        self.mixer.lock().unwrap().render(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::AudioInfo;
+    use crate::effect::{Chain, Convolution};
+
+    fn source(samples: Vec<f32>, strip: Strip) -> Source<f32> {
+        Source {
+            samples: Arc::new(Mutex::new(samples)),
+            info: AudioInfo {
+                sample_rate: 100,
+                channels: 1,
+                ..AudioInfo::default()
+            },
+            strip,
+            effects: Arc::new(Mutex::new(Chain::new())),
+        }
+    }
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn mixes_panned_sources() {
+        let left = Strip {
+            pan: -1.0,
+            ..Strip::default()
+        };
+        let right = Strip {
+            pan: 1.0,
+            gain_db: -6.0,
+            ..Strip::default()
+        };
+        let mut engine = Engine::null(100, 2);
+        engine.set_sources(vec![
+            source(vec![0.5, 0.25], left),
+            source(vec![1.0, 1.0], right),
+        ]);
+        engine.play().unwrap();
+
+        let mut out = [0.0; 4];
+        engine.render(&mut out);
+        let gain = 10f32.powf(-6.0 / 20.0);
+        assert_close(&out, &[0.5, gain, 0.25, gain]);
+        assert_eq!(engine.position(), 2);
+    }
+
+    #[test]
+    fn mute_and_solo_pick_what_is_heard() {
+        let mut engine = Engine::null(100, 1);
+        let strips = |first: Strip, second: Strip| {
+            vec![source(vec![0.5], first), source(vec![0.25], second)]
+        };
+        let muted = Strip {
+            mute: true,
+            ..Strip::default()
+        };
+        let soloed = Strip {
+            solo: true,
+            ..Strip::default()
+        };
+
+        for (sources, expected) in [
+            (strips(Strip::default(), Strip::default()), 0.75),
+            (strips(muted, Strip::default()), 0.25),
+            (strips(Strip::default(), soloed), 0.25),
+            (strips(soloed, soloed), 0.75),
+            (
+                strips(
+                    Strip {
+                        mute: true,
+                        ..soloed
+                    },
+                    soloed,
+                ),
+                0.25,
+            ),
+        ] {
+            engine.set_sources(sources);
+            engine.seek(0);
+            engine.play().unwrap();
+            let mut out = [0.0];
+            engine.render(&mut out);
+            assert_close(&out, &[expected]);
+        }
+    }
+
+    #[test]
+    fn playback_stops_at_the_end() {
+        let mut engine = Engine::null(100, 1);
+        engine.set_sources(vec![source(vec![0.1, 0.2, 0.3], Strip::default())]);
+        engine.play().unwrap();
+
+        let mut out = [1.0; 2];
+        engine.render(&mut out);
+        assert!(engine.is_playing());
+        engine.render(&mut out);
+        assert_close(&out, &[0.3, 0.0]);
+        assert!(!engine.is_playing());
+        assert_eq!(engine.position(), 3);
+
+        // Paused output is silent, and playing again starts over
+        engine.render(&mut out);
+        assert_close(&out, &[0.0, 0.0]);
+        engine.play().unwrap();
+        engine.render(&mut out);
+        assert_close(&out, &[0.1, 0.2]);
+    }
+
+    #[test]
+    fn reverbs_are_rebuilt_for_the_output_before_playing() {
+        let wet = source(vec![1.0], Strip::default());
+        wet.effects
+            .lock()
+            .unwrap()
+            .push(Box::new(Convolution::new(vec![1.0; 10], 100)));
+        let engine = Engine::<f32>::null(200, 1);
+        engine.set_sources(vec![wet.clone()]);
+
+        let effects = wet.effects.lock().unwrap();
+        assert!(effects.is_prepared(200));
+        assert_eq!(effects.get(0).unwrap().tail(), 20);
+    }
+
+    #[test]
+    fn stream_errors_wait_to_be_seen() {
+        let engine = Engine::<f32>::null(100, 1);
+        assert!(engine.error().is_none());
+        let mut report = report(engine.reporter.clone());
+        report(cpal::StreamError::DeviceNotAvailable);
+        assert!(matches!(engine.error(), Some(Error::Disconnected)));
+        assert!(engine.error().is_none());
+    }
+}
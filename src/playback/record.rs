@@ -0,0 +1,243 @@
+use super::{report, Error};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use dasp::Sample;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+// How much input the ring holds before it overflows, in seconds. Input is collected far more
+// often than this.
+const RING_SECONDS: u32 = 2;
+
+/// Captures audio into a shared sample buffer, mixing the input's channels down to mono.
+///
+/// Input goes through a lock-free ring, so the input callback never waits on the buffer or
+/// allocates. It reaches the buffer when `collect` is called, which should happen regularly while
+/// recording, and at the latest when recording stops.
+///
+/// Like `Engine`, a recorder either reads from a cpal input stream, or is a null input that
+/// captures nothing on its own: frames are then pushed with `capture`, which is how tests feed
+/// it synthetic input.
+pub struct Recorder<T> {
+    ring: Arc<Ring>,
+    recording: Arc<AtomicBool>,
+    target: Option<Arc<Mutex<Vec<T>>>>,
+    // Samples taken from the ring, on their way to the target.
+    collected: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+    stream: Option<cpal::Stream>,
+    reporter: Sender<Error>,
+    errors: Receiver<Error>,
+}
+
+impl<T> Recorder<T>
+where
+    T: Sample + Send + 'static,
+{
+    /// Opens the default input device of the default host.
+    pub fn new() -> Result<Self, Error> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or(Error::NoInputDevice)?;
+        let supported = device
+            .default_input_config()
+            .map_err(Error::DefaultStreamConfig)?;
+        let config: cpal::StreamConfig = supported.config();
+
+        let mut recorder = Self::null(config.sample_rate.0, config.channels);
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => recorder.build_stream::<f32>(&device, &config),
+            cpal::SampleFormat::I16 => recorder.build_stream::<i16>(&device, &config),
+            cpal::SampleFormat::U16 => recorder.build_stream::<u16>(&device, &config),
+        }?;
+        // Some hosts start streams as soon as they are built
+        stream.pause().map_err(Error::PauseStream)?;
+        recorder.stream = Some(stream);
+
+        Ok(recorder)
+    }
+
+    /// A recorder with no device behind it, taking `channels` channels at `sample_rate`.
+    pub fn null(sample_rate: u32, channels: u16) -> Self {
+        let (reporter, errors) = mpsc::channel();
+        Self {
+            ring: Arc::new(Ring::new((sample_rate.max(1) * RING_SECONDS) as usize)),
+            recording: Arc::new(AtomicBool::new(false)),
+            target: None,
+            collected: vec![],
+            sample_rate,
+            channels: channels.max(1),
+            stream: None,
+            reporter,
+            errors,
+        }
+    }
+
+    fn build_stream<S: cpal::Sample>(
+        &self,
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+    ) -> Result<cpal::Stream, Error> {
+        let ring = self.ring.clone();
+        let recording = self.recording.clone();
+        let channels = self.channels as usize;
+        device
+            .build_input_stream(
+                config,
+                move |data: &[S], _: &cpal::InputCallbackInfo| {
+                    if recording.load(Ordering::Relaxed) {
+                        for frame in data.chunks(channels) {
+                            let sum = frame.iter().map(|sample| sample.to_f32()).sum::<f32>();
+                            ring.push(sum / channels as f32);
+                        }
+                    }
+                },
+                report(self.reporter.clone()),
+            )
+            .map_err(Error::BuildStream)
+    }
+
+    /// The next error the input stream ran into, if there are any yet to be seen. After
+    /// `Error::Disconnected` nothing more will be captured, and a new recorder has to be opened.
+    pub fn error(&self) -> Option<Error> {
+        self.errors.try_recv().ok()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Starts capturing input to append to the end of `buffer`.
+    pub fn record(&mut self, buffer: Arc<Mutex<Vec<T>>>) -> Result<(), Error> {
+        // Anything left over from before belongs to no one
+        self.ring.pop_into(&mut self.collected);
+        self.collected.clear();
+
+        self.target = Some(buffer);
+        self.recording.store(true, Ordering::Relaxed);
+        if let Some(stream) = &self.stream {
+            stream.play().map_err(Error::PlayStream)?;
+        }
+        Ok(())
+    }
+
+    /// Stops capturing, appending the last of the input to the buffer.
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.recording.store(false, Ordering::Relaxed);
+        let paused = match &self.stream {
+            Some(stream) => stream.pause().map_err(Error::PauseStream),
+            None => Ok(()),
+        };
+        self.collect();
+        self.target = None;
+        paused
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// Appends the input captured so far to the buffer being recorded into.
+    pub fn collect(&mut self) {
+        self.ring.pop_into(&mut self.collected);
+        if let Some(target) = &self.target {
+            target.lock().unwrap().extend(
+                self.collected
+                    .iter()
+                    .map(|sample| T::Float::from_sample(*sample).to_sample::<T>()),
+            );
+        }
+        self.collected.clear();
+    }
+
+    /// Pushes interleaved frames into a null recorder. Recorders with a device capture from
+    /// their stream instead, so this should only be used on recorders made with `null`.
+    pub fn capture(&self, input: &[f32]) {
+        if self.is_recording() {
+            let channels = self.channels as usize;
+            for frame in input.chunks(channels) {
+                self.ring.push(frame.iter().sum::<f32>() / channels as f32);
+            }
+        }
+    }
+}
+
+// A fixed-size queue of samples for one thread to push to while another pops, without locks.
+// Samples pushed while it's full are dropped.
+struct Ring {
+    samples: Box<[AtomicU32]>,
+    // How many samples have ever been pushed and popped. Each is only changed by one side.
+    pushed: AtomicUsize,
+    popped: AtomicUsize,
+}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Self {
+            samples: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+            pushed: AtomicUsize::new(0),
+            popped: AtomicUsize::new(0),
+        }
+    }
+
+    fn push(&self, sample: f32) {
+        let pushed = self.pushed.load(Ordering::Relaxed);
+        let popped = self.popped.load(Ordering::Acquire);
+        if pushed.wrapping_sub(popped) < self.samples.len() {
+            self.samples[pushed % self.samples.len()].store(sample.to_bits(), Ordering::Relaxed);
+            self.pushed.store(pushed.wrapping_add(1), Ordering::Release);
+        }
+    }
+
+    // Moves every waiting sample onto the end of `out`.
+    fn pop_into(&self, out: &mut Vec<f32>) {
+        let popped = self.popped.load(Ordering::Relaxed);
+        let pushed = self.pushed.load(Ordering::Acquire);
+        let waiting = pushed.wrapping_sub(popped);
+        out.extend((0..waiting).map(|i| {
+            let index = popped.wrapping_add(i) % self.samples.len();
+            f32::from_bits(self.samples[index].load(Ordering::Relaxed))
+        }));
+        self.popped.store(pushed, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_a_mono_mixdown() {
+        let mut recorder = Recorder::<f32>::null(10, 2);
+        let buffer = Arc::new(Mutex::new(vec![1.0]));
+        recorder.capture(&[0.5, 0.5]);
+        recorder.record(buffer.clone()).unwrap();
+        recorder.capture(&[0.5, 0.5, 1.0, 0.0]);
+        recorder.collect();
+        recorder.capture(&[-1.0, -0.5]);
+        recorder.stop().unwrap();
+        recorder.capture(&[0.5, 0.5]);
+        recorder.collect();
+
+        assert!(!recorder.is_recording());
+        assert_eq!(*buffer.lock().unwrap(), vec![1.0, 0.5, 0.5, -0.75]);
+    }
+
+    #[test]
+    fn ring_drops_what_doesnt_fit() {
+        let ring = Ring::new(3);
+        for i in 0..5 {
+            ring.push(i as f32);
+        }
+        let mut out = vec![];
+        ring.pop_into(&mut out);
+        ring.push(5.0);
+        ring.pop_into(&mut out);
+        assert_eq!(out, vec![0.0, 1.0, 2.0, 5.0]);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::decode::{self, AudioInfo, Tags};
+use crate::effect::SavedEffect;
+use crate::export::{self, BitDepth};
+use crate::playback::Strip;
+use crate::style::Theme;
+use crate::widgets::spectrogram;
+use crate::{Error, Result};
+use dasp::Sample;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// The newest project file version this build reads and the one it writes.
+pub const VERSION: u32 = 1;
+
+/// A saved editing session.
+///
+/// Tracks refer back to the audio files they were imported from instead of embedding them. Tracks
+/// that have no source file, or whose samples no longer match it, have their samples written to
+/// a folder beside the project file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub theme: Theme,
+    /// The tracks in display order.
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    pub name: String,
+    pub info: AudioInfo,
+    pub tags: Tags,
+    pub source: Option<SourceRef>,
+    /// The track's own samples, overriding `source`, relative to the project file.
+    pub audio: Option<PathBuf>,
+    pub spectrogram: spectrogram::Settings,
+    /// Missing from projects saved before tracks had mixer settings.
+    #[serde(default)]
+    pub strip: Strip,
+    /// The track's effect chain, in processing order.
+    #[serde(default)]
+    pub effects: Vec<SavedEffect>,
+}
+
+/// One channel of an audio file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceRef {
+    /// Relative to the project file when the audio is stored beneath it.
+    pub path: PathBuf,
+    pub channel: usize,
+}
+
+impl Project {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            version: VERSION,
+            theme,
+            tracks: vec![],
+        }
+    }
+
+    /// Reads a project file, rejecting ones written by a newer version.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let project: Project = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if project.version > VERSION {
+            return Err(Error::ProjectVersion(project.version));
+        }
+        Ok(project)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Adds a track to a project being saved to `path`, with its source given as an absolute
+    /// path. Unless the track has a source that still describes `samples` exactly, the samples are
+    /// written out beside the project and the track refers to them instead.
+    pub fn push_track<T: Sample>(
+        &mut self,
+        path: &Path,
+        mut track: Track,
+        samples: &[T],
+    ) -> Result<()> {
+        let base = base_dir(path);
+        track.audio = None;
+
+        if track.source.is_none() && !samples.is_empty() {
+            let dir = audio_dir(path);
+            fs::create_dir_all(base.join(&dir))?;
+            let file = dir.join(format!("track_{}.wav", self.tracks.len() + 1));
+            let samples: Vec<f32> = samples
+                .iter()
+                .map(|s| s.to_float_sample().to_sample::<f32>())
+                .collect();
+            export::write_wav(
+                base.join(&file),
+                &samples,
+                export::Settings {
+                    bit_depth: BitDepth::Float32,
+                    sample_rate: track.info.sample_rate,
+                    channels: 1,
+                },
+            )?;
+            track.audio = Some(file);
+        }
+
+        if let Some(source) = &mut track.source {
+            source.path = relative_to(&source.path, &base);
+        }
+
+        self.tracks.push(track);
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Decodes this track's samples, resolving paths against the project file at `path`.
+    pub fn load_samples<T: Sample>(&self, path: &Path) -> Result<Vec<T>> {
+        let base = base_dir(path);
+        if let Some(audio) = &self.audio {
+            return Ok(decode::wav::open(base.join(audio))?.samples);
+        }
+        match &self.source {
+            Some(source) => Ok(decode::open(base.join(&source.path))?
+                .deinterleave()
+                .into_iter()
+                .nth(source.channel)
+                .unwrap_or_default()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// The source reference with its path resolved against the project file at `path`.
+    pub fn resolved_source(&self, path: &Path) -> Option<SourceRef> {
+        self.source.as_ref().map(|source| SourceRef {
+            path: base_dir(path).join(&source.path),
+            channel: source.channel,
+        })
+    }
+}
+
+fn base_dir(path: &Path) -> PathBuf {
+    path.parent().map_or(PathBuf::new(), Path::to_path_buf)
+}
+
+// The folder, relative to the project file, holding its tracks' own samples.
+fn audio_dir(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map_or(String::from("project"), |s| {
+        s.to_string_lossy().into_owned()
+    });
+    PathBuf::from(format!("{} audio", stem))
+}
+
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    path.strip_prefix(base)
+        .map_or(path.to_path_buf(), Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::{Chain, Convolution, Delay, Effect, Registry};
+
+    // A folder in the temporary directory, removed with everything in it when dropped.
+    struct Folder(PathBuf);
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn track(name: &str, source: Option<SourceRef>, effects: &Chain) -> Track {
+        Track {
+            name: name.to_string(),
+            info: AudioInfo {
+                sample_rate: 8000,
+                bits_per_sample: 32,
+                channels: 1,
+            },
+            tags: Tags::default(),
+            source,
+            audio: None,
+            spectrogram: spectrogram::Settings::default(),
+            strip: Strip {
+                gain_db: -3.0,
+                pan: 0.25,
+                ..Strip::default()
+            },
+            effects: effects.save(),
+        }
+    }
+
+    #[test]
+    fn saved_projects_load_as_they_were() {
+        let folder = Folder(
+            std::env::temp_dir().join(format!("impulse-editor-project-{}", std::process::id())),
+        );
+        fs::create_dir_all(&folder.0).unwrap();
+        let path = folder.0.join("session.impulse");
+
+        // A stereo file, of which the second channel is a track
+        let stereo = [0.1f32, -0.1, 0.2, -0.2, 0.3, -0.3];
+        let file = folder.0.join("source.wav");
+        export::write_wav(
+            &file,
+            &stereo,
+            export::Settings {
+                bit_depth: BitDepth::Float32,
+                sample_rate: 8000,
+                channels: 2,
+            },
+        )
+        .unwrap();
+        let source = SourceRef {
+            path: file.clone(),
+            channel: 1,
+        };
+
+        let mut effects = Chain::new();
+        effects.push(Box::new(Delay::default()));
+        effects.push(Box::new(Convolution::with_channels(
+            vec![vec![1.0, 0.5], vec![0.25]],
+            8000,
+        )));
+
+        let mut project = Project::new(Theme::Dark);
+        let recorded = [0.5f32, -0.25, 0.125];
+        project
+            .push_track(
+                &path,
+                track("Imported", Some(source.clone()), &effects),
+                &[-0.1f32, -0.2, -0.3],
+            )
+            .unwrap();
+        project
+            .push_track(&path, track("Recorded", None, &Chain::new()), &recorded)
+            .unwrap();
+        project.write(&path).unwrap();
+
+        let read = Project::read(&path).unwrap();
+        assert_eq!(read.theme, Theme::Dark);
+        assert_eq!(read.tracks.len(), 2);
+        let (imported, recording) = (&read.tracks[0], &read.tracks[1]);
+
+        // Paths are kept relative to the project, so it can be moved along with its audio
+        assert_eq!(
+            imported.source.as_ref().unwrap().path,
+            PathBuf::from("source.wav")
+        );
+        assert_eq!(imported.resolved_source(&path), Some(source));
+        assert_eq!(
+            imported.load_samples::<f32>(&path).unwrap(),
+            vec![-0.1, -0.2, -0.3]
+        );
+        assert_eq!(imported.strip, project.tracks[0].strip);
+
+        assert_eq!(recording.name, "Recorded");
+        assert!(recording.audio.is_some());
+        assert_eq!(
+            recording.load_samples::<f32>(&path).unwrap(),
+            recorded.to_vec()
+        );
+
+        let registry = Registry::default();
+        let restored: Vec<Box<dyn Effect>> = imported
+            .effects
+            .iter()
+            .map(|saved| registry.restore(saved).unwrap())
+            .collect();
+        assert_eq!(restored.len(), 2);
+        for (restored, saved) in restored.iter().zip(effects.save()) {
+            assert_eq!(restored.name(), saved.name);
+            assert_eq!(restored.save(), saved.state);
+        }
+        assert_eq!(restored[1].channels(), 2);
+    }
+}
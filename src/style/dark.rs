@@ -1,5 +1,6 @@
 use iced::{
-    button, checkbox, container, progress_bar, radio, rule, scrollable, slider, text_input, Color,
+    button, checkbox, container, pick_list, progress_bar, radio, rule, scrollable, slider,
+    text_input, Color,
 };
 
 const BACKGROUND: Color = Color::from_rgb(
@@ -184,6 +185,39 @@ impl scrollable::StyleSheet for Scrollable {
     }
 }
 
+pub struct PickList;
+
+impl pick_list::StyleSheet for PickList {
+    fn menu(&self) -> pick_list::Menu {
+        pick_list::Menu {
+            text_color: Color::WHITE,
+            background: SURFACE.into(),
+            border_width: 1.0,
+            border_color: ACTIVE,
+            selected_text_color: Color::WHITE,
+            selected_background: ACTIVE.into(),
+        }
+    }
+
+    fn active(&self) -> pick_list::Style {
+        pick_list::Style {
+            text_color: Color::WHITE,
+            background: SURFACE.into(),
+            border_radius: 2.0,
+            border_width: 1.0,
+            border_color: ACTIVE,
+            icon_size: 0.7,
+        }
+    }
+
+    fn hovered(&self) -> pick_list::Style {
+        pick_list::Style {
+            border_color: ACCENT,
+            ..self.active()
+        }
+    }
+}
+
 pub struct Slider;
 
 impl slider::StyleSheet for Slider {
@@ -2,11 +2,14 @@ pub mod dark;
 pub mod light;
 
 use iced::{
-    button, checkbox, container, progress_bar, radio, rule, scrollable, slider, text_input,
+    button, checkbox, container, pick_list, progress_bar, radio, rule, scrollable, slider,
+    text_input, Color,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Theme {
+    #[default]
     Light,
     Dark,
 }
@@ -15,9 +18,17 @@ impl Theme {
     pub const ALL: [Theme; 2] = [Theme::Light, Theme::Dark];
 }
 
-impl Default for Theme {
-    fn default() -> Theme {
-        Theme::Light
+/// The banner errors are reported in, which looks the same in every theme.
+pub struct ErrorBanner;
+
+impl container::StyleSheet for ErrorBanner {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: Color::from_rgb8(0xB0, 0x30, 0x30).into(),
+            text_color: Color::WHITE.into(),
+            border_radius: 3.0,
+            ..container::Style::default()
+        }
     }
 }
 
@@ -66,6 +77,15 @@ impl From<Theme> for Box<dyn scrollable::StyleSheet> {
     }
 }
 
+impl From<Theme> for Box<dyn pick_list::StyleSheet> {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Light => Default::default(),
+            Theme::Dark => dark::PickList.into(),
+        }
+    }
+}
+
 impl From<Theme> for Box<dyn slider::StyleSheet> {
     fn from(theme: Theme) -> Self {
         match theme {
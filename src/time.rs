@@ -0,0 +1,41 @@
+use iced_native::futures::{channel::mpsc, stream::BoxStream, StreamExt};
+use iced_native::subscription::{Recipe, Subscription};
+use iced_native::{event, Event, Hasher};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A subscription that produces the current time every `interval`, for as long as it's kept.
+///
+/// Ticks come from a thread of their own, so this doesn't depend on which async runtime iced was
+/// built with.
+pub fn every(interval: Duration) -> Subscription<Instant> {
+    Subscription::from_recipe(Every(interval))
+}
+
+struct Every(Duration);
+
+impl Recipe<Hasher, (Event, event::Status)> for Every {
+    type Output = Instant;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+
+        std::any::TypeId::of::<Self>().hash(state);
+        self.0.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<(Event, event::Status)>) -> BoxStream<Instant> {
+        let (sender, receiver) = mpsc::unbounded();
+        let interval = self.0;
+
+        // The thread stops once the subscription is dropped and the receiver with it
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if sender.unbounded_send(Instant::now()).is_err() {
+                break;
+            }
+        });
+
+        receiver.boxed()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::decode::{AudioInfo, Tags};
+use crate::effect::Chain;
+use crate::playback::{Source, Strip};
+use crate::project::SourceRef;
+use crate::widgets::spectrogram::{BufferSize, View};
+use crate::widgets::Spectrogram;
+use dasp::Sample;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+pub type Buffer<T> = Arc<Mutex<Vec<T>>>;
+
+/// A mono track of samples.
+pub struct Channel<T> {
+    pub name: String,
+    pub info: AudioInfo,
+    pub tags: Tags,
+    /// The file channel the samples were imported from, while they still match it.
+    pub source: Option<SourceRef>,
+    pub samples: Buffer<T>,
+    pub strip: Strip,
+    pub effects: Arc<Mutex<Chain>>,
+    channel: (Sender<View<T>>, Receiver<View<T>>),
+}
+
+impl<T> Channel<T>
+where
+    T: Sample,
+{
+    pub fn new() -> Self {
+        Self {
+            name: String::from("Untitled"),
+            info: AudioInfo::default(),
+            tags: Tags::default(),
+            source: None,
+            channel: mpsc::channel(),
+            samples: Arc::new(Mutex::new(vec![])),
+            strip: Strip::default(),
+            effects: Arc::new(Mutex::new(Chain::new())),
+        }
+    }
+    pub fn assign_sender(&self) -> Sender<View<T>> {
+        self.channel.0.clone()
+    }
+    /// The samples as the playback mixer reads them.
+    pub fn mixer_source(&self) -> Source<T> {
+        Source {
+            samples: self.samples.clone(),
+            info: self.info,
+            strip: self.strip,
+            effects: self.effects.clone(),
+        }
+    }
+    /// A copy of the samples as floats, for processing and analysis.
+    pub fn float_samples(&self) -> Vec<f32> {
+        self.samples
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|s| s.to_float_sample().to_sample::<f32>())
+            .collect()
+    }
+    /// Pairs the channel with a new spectrogram showing all of its samples.
+    pub fn with_spectrogram(self) -> (Self, Spectrogram<T>) {
+        let mut spectrogram = Spectrogram::new(self.assign_sender());
+        spectrogram.load(self.samples.clone(), BufferSize::All);
+        (self, spectrogram)
+    }
+}
+
+impl<T: Sample> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The tracks of a session, each drawn by the spectrogram at the same index.
+pub struct Tracks<T> {
+    pub channels: Vec<Channel<T>>,
+    pub spectrograms: Vec<Spectrogram<T>>,
+}
+
+impl<T> Tracks<T> {
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Takes `count` tracks out, starting at `index`.
+    pub fn take(&mut self, index: usize, count: usize) -> Vec<(Channel<T>, Spectrogram<T>)> {
+        let end = (index + count).min(self.len());
+        self.channels
+            .drain(index..end)
+            .zip(self.spectrograms.drain(index..end))
+            .collect()
+    }
+
+    /// Puts tracks back in, the first of them at `index`.
+    pub fn put(&mut self, index: usize, tracks: Vec<(Channel<T>, Spectrogram<T>)>) {
+        for (i, (channel, spectrogram)) in tracks.into_iter().enumerate() {
+            self.channels.insert(index + i, channel);
+            self.spectrograms.insert(index + i, spectrogram);
+        }
+    }
+}
+
+impl<T> Tracks<T>
+where
+    T: Sample,
+{
+    /// Copies the selected samples, or returns `None` if the selection is empty or off the tracks.
+    pub fn copy(&self, selection: &Selection) -> Option<Clip<T>> {
+        let channel = self.channels.get(selection.track)?;
+        let samples = channel.samples.lock().unwrap();
+        let range =
+            selection.range.start.min(samples.len())..selection.range.end.min(samples.len());
+        if range.is_empty() {
+            return None;
+        }
+        Some(Clip {
+            info: channel.info,
+            samples: samples[range].to_vec(),
+        })
+    }
+}
+
+impl<T> Default for Tracks<T> {
+    fn default() -> Self {
+        Self {
+            channels: vec![],
+            spectrograms: vec![],
+        }
+    }
+}
+
+/// A span of samples on one track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub track: usize,
+    pub range: Range<usize>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+/// Samples copied from a track, along with the rate they were recorded at.
+#[derive(Clone)]
+pub struct Clip<T> {
+    pub info: AudioInfo,
+    pub samples: Vec<T>,
+}
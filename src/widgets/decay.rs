@@ -0,0 +1,195 @@
+use iced_graphics::{
+    triangle::{Mesh2D, Vertex2D},
+    Backend, Defaults, Primitive, Renderer,
+};
+use iced_native::{
+    layout, mouse, Color, Element, Font, Hasher, HorizontalAlignment, Layout, Length, Point,
+    Rectangle, Size, Vector, VerticalAlignment, Widget,
+};
+
+use super::timeline::strip;
+
+const HEIGHT: f32 = 180.0;
+
+// The range shown, in dB.
+const FLOOR_DB: f32 = -80.0;
+const GRID_DB: f32 = 20.0;
+
+const GRID_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.5);
+const LINE_WIDTH: f32 = 1.5;
+
+/// A plot of energy decay curves, in dB over time.
+pub struct Decay<'a> {
+    curves: Vec<(&'a [f32], Color)>,
+    step: f32,
+}
+
+impl<'a> Decay<'a> {
+    /// Creates a plot of curves with a point every `step` seconds.
+    pub fn new(step: f32) -> Self {
+        Self {
+            curves: vec![],
+            step,
+        }
+    }
+
+    /// Adds a curve, drawn over the ones before it.
+    pub fn push(mut self, curve: &'a [f32], color: Color) -> Self {
+        self.curves.push((curve, color));
+        self
+    }
+
+    // The length of the longest curve, in seconds.
+    fn duration(&self) -> f32 {
+        self.curves
+            .iter()
+            .map(|(curve, _)| curve.len())
+            .max()
+            .unwrap_or(0) as f32
+            * self.step
+    }
+}
+
+impl<'a, Message, B> Widget<Message, Renderer<B>> for Decay<'a>
+where
+    B: Backend,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Units(HEIGHT as u16)
+    }
+
+    fn layout(&self, _renderer: &Renderer<B>, limits: &layout::Limits) -> layout::Node {
+        let size = limits
+            .width(Length::Fill)
+            .height(Length::Units(HEIGHT as u16))
+            .resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn hash_layout(&self, _state: &mut Hasher) {}
+
+    fn draw(
+        &self,
+        _renderer: &mut Renderer<B>,
+        _defaults: &Defaults,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) -> (Primitive, mouse::Interaction) {
+        let b = layout.bounds();
+        let duration = self.duration();
+        if duration <= 0.0 || b.width < 1.0 {
+            return (Primitive::None, mouse::Interaction::default());
+        }
+
+        let mut primitives = vec![];
+        let mut db = 0.0;
+        while db >= FLOOR_DB {
+            let y = b.y + db / FLOOR_DB * (b.height - 1.0);
+            primitives.push(strip(b.x, y, 1.0, b.width, GRID_COLOR));
+            // The lowest label sits above its line, inside the plot
+            let label_y = if db > FLOOR_DB { y } else { y - 15.0 };
+            primitives.push(label(
+                format!("{} dB", db),
+                Point::new(b.x + 2.0, label_y),
+                HorizontalAlignment::Left,
+            ));
+            db -= GRID_DB;
+        }
+        primitives.push(label(
+            format!("{:.2} s", duration),
+            Point::new(b.x + b.width - 62.0, b.y + b.height - 15.0),
+            HorizontalAlignment::Right,
+        ));
+
+        let size = Size::new(b.width, b.height);
+        for (curve, color) in self.curves.iter() {
+            primitives.push(Primitive::Translate {
+                translation: Vector::new(b.x, b.y),
+                content: Box::new(Primitive::Mesh2D {
+                    size,
+                    buffers: line(curve, self.step, duration, *color, size),
+                }),
+            });
+        }
+
+        (
+            Primitive::Group { primitives },
+            mouse::Interaction::default(),
+        )
+    }
+}
+
+// A small grid label with its top at `position`.
+fn label(content: String, position: Point, alignment: HorizontalAlignment) -> Primitive {
+    Primitive::Text {
+        content,
+        bounds: Rectangle {
+            x: position.x,
+            y: position.y + 1.0,
+            width: 60.0,
+            height: 14.0,
+        },
+        color: GRID_COLOR,
+        size: 12.0,
+        font: Font::Default,
+        horizontal_alignment: alignment,
+        vertical_alignment: VerticalAlignment::Top,
+    }
+}
+
+// Builds a curve as a strip of quads, one per segment, with at most a point per pixel.
+fn line(curve: &[f32], step: f32, duration: f32, color: Color, size: Size) -> Mesh2D {
+    let stride = ((curve.len() as f32 / size.width).ceil() as usize).max(1);
+    let points: Vec<(f32, f32)> = curve
+        .iter()
+        .enumerate()
+        .step_by(stride)
+        .map(|(i, db)| {
+            (
+                i as f32 * step / duration * size.width,
+                db.clamp(FLOOR_DB, 0.0) / FLOOR_DB * (size.height - 1.0),
+            )
+        })
+        .collect();
+
+    let color = [color.r, color.g, color.b, color.a];
+    let mut vertices = Vec::with_capacity(points.len().saturating_sub(1) * 4);
+    let mut indices = Vec::with_capacity(points.len().saturating_sub(1) * 6);
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        let length = (x1 - x0).hypot(y1 - y0).max(f32::EPSILON);
+        let (nx, ny) = (
+            -(y1 - y0) / length * LINE_WIDTH / 2.0,
+            (x1 - x0) / length * LINE_WIDTH / 2.0,
+        );
+
+        let first = vertices.len() as u32;
+        for position in [
+            [x0 + nx, y0 + ny],
+            [x0 - nx, y0 - ny],
+            [x1 + nx, y1 + ny],
+            [x1 - nx, y1 - ny],
+        ] {
+            vertices.push(Vertex2D { position, color });
+        }
+        indices.extend_from_slice(&[first, first + 1, first + 2, first + 1, first + 3, first + 2]);
+    }
+
+    Mesh2D { vertices, indices }
+}
+
+impl<'a, Message, B> From<Decay<'a>> for Element<'a, Message, Renderer<B>>
+where
+    B: Backend,
+    Message: 'a,
+{
+    fn from(decay: Decay<'a>) -> Self {
+        Element::new(decay)
+    }
+}
@@ -1,2 +1,6 @@
+pub mod decay;
 pub mod spectrogram;
+pub mod timeline;
+pub use decay::Decay;
 pub use spectrogram::Spectrogram;
+pub use timeline::Timeline;
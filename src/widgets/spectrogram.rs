@@ -3,46 +3,430 @@ use iced_graphics::{
     Backend, Defaults, Primitive, Renderer,
 };
 use iced_native::{
-    layout, mouse, Element, Hasher, Layout, Length, Point, Rectangle, Size, Vector, Widget,
+    event, keyboard, layout, mouse, Clipboard, Color, Element, Event, Hasher, Layout, Length,
+    Point, Rectangle, Size, Vector, Widget,
 };
+use std::ops::Range;
 use std::sync::{mpsc::Sender, Arc, Mutex};
 
+use super::timeline::{strip, PLAYHEAD_COLOR};
+use crate::dsp::{Stft, Window};
+use crate::{Error, Result};
 use dasp::Sample;
+use serde::{Deserialize, Serialize};
 
+// The smallest on-screen size, in pixels, of one spectrogram cell.
+const CELL_SIZE: f32 = 2.0;
+
+// The default height of a spectrogram, in pixels.
+const HEIGHT: u16 = 240;
+
+// How much one notch of the mouse wheel zooms in, and how much of the view it pans.
+const WHEEL_ZOOM: f32 = 1.25;
+const WHEEL_PAN: f32 = 0.1;
+
+/// How much of a sample buffer to view or post.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BufferSize {
     All,
+    /// The samples from the first index up to (not including) the second. Indices past the end of
+    /// the buffer are clamped when the buffer is read, so a window may be set up before it fills.
     Range(usize, usize),
 }
 
+impl BufferSize {
+    /// The span of a buffer of `len` samples that this covers.
+    pub fn span(&self, len: usize) -> Range<usize> {
+        match *self {
+            BufferSize::All => 0..len,
+            BufferSize::Range(start, end) => {
+                let start = start.min(len);
+                start..end.clamp(start, len)
+            }
+        }
+    }
+}
+
+/// A span of a shared sample buffer, as posted by a `Spectrogram`. Only the handle to the buffer
+/// is cloned, never the samples themselves.
+#[derive(Clone)]
+pub struct View<T> {
+    pub samples: Arc<Mutex<Vec<T>>>,
+    pub buffersize: BufferSize,
+}
+
+/// How a `Spectrogram` analyses and colours its samples.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub fft_size: usize,
+    pub hop_size: usize,
+    pub window: Window,
+    /// Magnitudes at or below this level are drawn black.
+    pub db_floor: f32,
+    /// Magnitudes at or above this level are drawn white.
+    pub db_ceiling: f32,
+}
+
+impl Settings {
+    pub const FFT_SIZES: [usize; 6] = [256, 512, 1024, 2048, 4096, 8192];
+    pub const HOP_SIZES: [usize; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fft_size: 1024,
+            hop_size: 256,
+            window: Window::Hann,
+            db_floor: -100.0,
+            db_ceiling: 0.0,
+        }
+    }
+}
+
+/// The part of the session shown by every spectrogram. It's shared between tracks so that they
+/// stay aligned in time whatever their sample rates or lengths.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Seconds from the start of the session to the left edge.
+    pub start: f64,
+    /// How many times the session is magnified. At 1 all of it is shown.
+    pub zoom: f64,
+    /// The lowest and highest frequencies shown, as fractions of the Nyquist frequency.
+    pub frequencies: (f32, f32),
+}
+
+impl Viewport {
+    pub const MAX_ZOOM: f64 = 100_000.0;
+    // The narrowest frequency band that can be zoomed into.
+    const MIN_BAND: f32 = 0.01;
+
+    /// The seconds shown, for a session `total` seconds long.
+    pub fn duration(&self, total: f64) -> f64 {
+        total / self.zoom
+    }
+
+    /// The samples shown of a track recorded at `sample_rate`.
+    pub fn buffersize(&self, total: f64, sample_rate: u32) -> BufferSize {
+        if total <= 0.0 {
+            return BufferSize::All;
+        }
+        let rate = sample_rate as f64;
+        BufferSize::Range(
+            (self.start * rate).round() as usize,
+            ((self.start + self.duration(total)) * rate).round() as usize,
+        )
+    }
+
+    /// Applies a zoom or pan asked for with the mouse, for a session `total` seconds long.
+    pub fn navigate(&mut self, navigate: Navigate, total: f64) {
+        let duration = self.duration(total);
+        match navigate {
+            Navigate::Zoom { factor, at } => {
+                let time = self.start + at as f64 * duration;
+                self.zoom = (self.zoom * factor as f64).clamp(1.0, Self::MAX_ZOOM);
+                self.start = time - at as f64 * self.duration(total);
+            }
+            Navigate::Pan(amount) => self.start += amount as f64 * duration,
+            Navigate::ZoomFrequency { factor, at } => {
+                let (low, high) = self.frequencies;
+                let frequency = low + at * (high - low);
+                let band = ((high - low) / factor).clamp(Self::MIN_BAND, 1.0);
+                let low = (frequency - at * band).clamp(0.0, 1.0 - band);
+                self.frequencies = (low, low + band);
+            }
+        }
+        self.start = self
+            .start
+            .clamp(0.0, (total - self.duration(total)).max(0.0));
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            zoom: 1.0,
+            frequencies: (0.0, 1.0),
+        }
+    }
+}
+
+/// A change to the `Viewport` asked for with the mouse wheel. Positions are fractions of the
+/// spectrogram's width, or of its height from the bottom for frequencies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Navigate {
+    /// Ctrl+wheel: magnify time by `factor`, keeping the time at `at` in place.
+    Zoom { factor: f32, at: f32 },
+    /// Shift+wheel: move by a fraction of the time shown.
+    Pan(f32),
+    /// Alt+wheel: magnify frequency by `factor`, keeping the frequency at `at` in place.
+    ZoomFrequency { factor: f32, at: f32 },
+}
+
 #[derive(Clone)]
 pub struct Spectrogram<T> {
     samples: Arc<Mutex<Vec<T>>>,
-    sender: Sender<Arc<Mutex<Vec<T>>>>,
+    buffersize: BufferSize,
+    sender: Sender<View<T>>,
+    settings: Settings,
+    analysis: Arc<Mutex<Analysis>>,
+    // The selected samples, drawn highlighted.
+    selection: Option<Range<usize>>,
+    interaction: Arc<Mutex<Interaction>>,
+    frequencies: (f32, f32),
+    height: Length,
+    // The sample being played, drawn as a line.
+    playhead: Option<usize>,
+}
+
+// The state of a mouse selection in progress, shared between clones so that it survives the
+// widget being rebuilt for every view.
+#[derive(Default)]
+struct Interaction {
+    // The sample a drag started from, while the button is held.
+    anchor: Option<usize>,
+    last_click: Option<mouse::Click>,
+    modifiers: keyboard::Modifiers,
+}
+
+// The STFT of the frames around the span being viewed, and the last mesh drawn from it. Frames
+// are analysed as a view first needs them, along with one FFT's worth either side, and kept while
+// the buffer and the settings stay the same, so zooming and panning within them just picks out
+// different frames. When samples are appended only the frames reaching past the old end are
+// analysed again. The mesh is rebuilt when those frames or the widget's size change, since `draw`
+// runs on every frame.
+#[derive(Default)]
+struct Analysis {
+    // The address of the buffer `stft` was computed from, and how many samples it held.
+    source: (usize, usize),
+    settings: Option<Settings>,
+    // The index of the first frame in `stft`, counting from the start of the buffer.
+    first: usize,
+    stft: Stft,
+    mesh: Option<CachedMesh>,
+}
+
+// A mesh and the size, frequency band and STFT frames it was built for.
+struct CachedMesh {
+    size: Size,
+    frequencies: (f32, f32),
+    frames: Range<usize>,
+    mesh: Mesh2D,
 }
 
 impl<T> Spectrogram<T>
 where
     T: Sample,
 {
-    pub fn default(sender: Sender<Arc<Mutex<Vec<T>>>>) -> Self {
+    pub fn default(sender: Sender<View<T>>) -> Self {
         Self {
             samples: Arc::new(Mutex::new(vec![])),
+            buffersize: BufferSize::All,
             sender,
+            settings: Settings::default(),
+            analysis: Arc::new(Mutex::new(Analysis::default())),
+            selection: None,
+            interaction: Arc::new(Mutex::new(Interaction::default())),
+            frequencies: (0.0, 1.0),
+            height: Length::Units(HEIGHT),
+            playhead: None,
         }
     }
-    pub fn new(sender: Sender<Arc<Mutex<Vec<T>>>>) -> Self {
+    pub fn new(sender: Sender<View<T>>) -> Self {
         Self::default(sender)
     }
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+    /// Sets the number of samples per analysis frame, rounded up to a power of two.
+    pub fn fft_size(mut self, fft_size: usize) -> Self {
+        self.settings.fft_size = fft_size.max(2).next_power_of_two();
+        self
+    }
+    /// Sets the number of samples between the starts of consecutive frames.
+    pub fn hop_size(mut self, hop_size: usize) -> Self {
+        self.settings.hop_size = hop_size.max(1);
+        self
+    }
+    pub fn window(mut self, window: Window) -> Self {
+        self.settings.window = window;
+        self
+    }
+    /// Sets the range of magnitudes spread across the colour map. The ceiling is kept above the floor.
+    pub fn db_range(mut self, floor: f32, ceiling: f32) -> Self {
+        self.settings.db_floor = floor;
+        self.settings.db_ceiling = ceiling.max(floor + 1.0);
+        self
+    }
+    /// Shares `samples` with this spectrogram, which will only analyse and draw the span given by
+    /// `buffersize`.
     pub fn load(&mut self, samples: Arc<Mutex<Vec<T>>>, buffersize: BufferSize) {
-        match buffersize {
-            BufferSize::All => self.samples = samples,
-            _ => (),
+        self.samples = samples;
+        self.buffersize = buffersize;
+    }
+    /// Shows only the frequencies between `low` and `high`, given as fractions of the Nyquist
+    /// frequency.
+    pub fn frequencies(mut self, (low, high): (f32, f32)) -> Self {
+        self.frequencies = (low.clamp(0.0, 1.0), high.clamp(low, 1.0));
+        self
+    }
+    pub fn height(mut self, height: Length) -> Self {
+        self.height = height;
+        self
+    }
+    /// Highlights the given samples, indexed from the start of the whole buffer.
+    pub fn selection(mut self, selection: Option<Range<usize>>) -> Self {
+        self.selection = selection;
+        self
+    }
+    /// Marks the sample being played.
+    pub fn playhead(mut self, playhead: Option<usize>) -> Self {
+        self.playhead = playhead;
+        self
+    }
+    /// Lets the user select samples with the mouse, reporting each change through `on_select`.
+    pub fn on_select<'a, Message>(
+        self,
+        on_select: impl Fn(Range<usize>) -> Message + 'a,
+    ) -> Interactive<'a, T, Message> {
+        Interactive {
+            spectrogram: self,
+            on_select: Box::new(on_select),
+            on_navigate: None,
         }
     }
-    pub fn post(&self, buffersize: BufferSize) {
-        match buffersize {
-            BufferSize::All => self.sender.send(self.samples.clone()).unwrap(),
-            _ => (),
+    /// Analyses the samples again on the next draw, after they were changed in place.
+    pub fn refresh(&self) {
+        self.analysis.lock().unwrap().settings = None;
+    }
+    /// Sends the given span of the loaded samples to this spectrogram's channel.
+    pub fn post(&self, buffersize: BufferSize) -> Result<()> {
+        self.sender
+            .send(View {
+                samples: self.samples.clone(),
+                buffersize,
+            })
+            .map_err(|_| Error::Disconnected)
+    }
+
+    // The samples spread across the widget's width. Unlike the span that is analysed, this isn't
+    // clamped to the buffer, so a short track lines up with longer ones.
+    fn shown(&self, len: usize) -> Range<usize> {
+        match self.buffersize {
+            BufferSize::All => 0..len.max(1),
+            BufferSize::Range(start, end) => start..end.max(start + 1),
+        }
+    }
+
+    // The sample under horizontal position `x`, clamped to the buffer.
+    fn sample_at(&self, bounds: Rectangle, x: f32) -> usize {
+        let len = self.samples.lock().unwrap().len();
+        let shown = self.shown(len);
+        let t = ((x - bounds.x) / bounds.width).clamp(0.0, 1.0) as f64;
+        (shown.start + (t * shown.len() as f64).round() as usize).min(len)
+    }
+
+    // The horizontal position of sample `index`, clamped to the bounds.
+    fn x_of(&self, bounds: Rectangle, index: usize) -> f32 {
+        let shown = self.shown(self.samples.lock().unwrap().len());
+        let t = (index.clamp(shown.start, shown.end) - shown.start) as f64 / shown.len() as f64;
+        bounds.x + t as f32 * bounds.width
+    }
+
+    fn mesh(&self, size: Size) -> Mesh2D {
+        let mut analysis = self.analysis.lock().unwrap();
+
+        let settings = self.settings;
+        let reanalyse = match analysis.settings {
+            Some(old) => {
+                old.fft_size != settings.fft_size
+                    || old.hop_size != settings.hop_size
+                    || old.window != settings.window
+            }
+            None => true,
+        };
+
+        let samples = self.samples.lock().unwrap();
+        let buffer = Arc::as_ptr(&self.samples) as usize;
+        let hop = settings.hop_size.max(1);
+        let span = self.buffersize.span(samples.len());
+        // The frames starting within the span, out of every frame the buffer has
+        let total = samples.len().div_ceil(hop);
+        let frames = (span.start / hop).min(total)..span.end.div_ceil(hop).min(total);
+        let pad = settings.fft_size.div_ceil(hop);
+
+        let (analysed, analysed_len) = analysis.source;
+        let kept = if reanalyse || analysed != buffer || samples.len() < analysed_len {
+            None
+        } else {
+            // A buffer that only grew, as while recording, keeps the frames within its old end
+            if samples.len() > analysed_len {
+                let unchanged = analysed_len.saturating_sub(analysis.first * hop);
+                analysis.stft.truncate(unchanged);
+                analysis.mesh = None;
+            }
+            Some(analysis.first..analysis.first + analysis.stft.frames.len())
+        };
+        match kept {
+            Some(kept) if kept.start <= frames.start && frames.end <= kept.end => {}
+            Some(kept) if kept.start <= frames.start && frames.start <= kept.end => {
+                let end = (frames.end + pad).min(total);
+                let window = settings.window.coefficients(settings.fft_size);
+                analysis
+                    .stft
+                    .extend(&analyse(&samples, kept.end..end, &settings), &window);
+                analysis.stft.frames.truncate(end - kept.start);
+                analysis.mesh = None;
+            }
+            _ => {
+                let first = frames.start.saturating_sub(pad);
+                let end = (frames.end + pad).min(total);
+                let window = settings.window.coefficients(settings.fft_size);
+                analysis.stft = Stft::new(&analyse(&samples, first..end, &settings), &window, hop);
+                analysis.stft.frames.truncate(end.saturating_sub(first));
+                analysis.first = first;
+                analysis.mesh = None;
+            }
+        }
+        analysis.source = (buffer, samples.len());
+        drop(samples);
+
+        if analysis.settings != Some(settings) {
+            analysis.settings = Some(settings);
+            analysis.mesh = None;
+        }
+
+        let frequencies = self.frequencies;
+        match &analysis.mesh {
+            Some(cached)
+                if cached.size == size
+                    && cached.frequencies == frequencies
+                    && cached.frames == frames =>
+            {
+                cached.mesh.clone()
+            }
+            _ => {
+                let held = frames.start - analysis.first..frames.end - analysis.first;
+                let mesh = build_mesh(
+                    &analysis.stft.frames[held],
+                    analysis.stft.bins(),
+                    &settings,
+                    frequencies,
+                    size,
+                );
+                analysis.mesh = Some(CachedMesh {
+                    size,
+                    frequencies,
+                    frames,
+                    mesh: mesh.clone(),
+                });
+                mesh
+            }
         }
     }
 }
@@ -57,121 +441,509 @@ where
     }
 
     fn height(&self) -> Length {
-        Length::Shrink
+        self.height
     }
 
     fn layout(&self, _renderer: &Renderer<B>, limits: &layout::Limits) -> layout::Node {
-        let size = limits.width(Length::Fill).resolve(Size::ZERO);
+        let size = limits
+            .width(Length::Fill)
+            .height(self.height)
+            .resolve(Size::ZERO);
 
-        layout::Node::new(Size::new(size.width, size.width))
+        layout::Node::new(size)
     }
 
-    fn hash_layout(&self, _state: &mut Hasher) {}
+    fn hash_layout(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+
+        self.height.hash(state);
+    }
 
     fn draw(
         &self,
         _renderer: &mut Renderer<B>,
         _defaults: &Defaults,
         layout: Layout<'_>,
-        cursor_position: Point,
+        _cursor_position: Point,
         _viewport: &Rectangle,
     ) -> (Primitive, mouse::Interaction) {
         let b = layout.bounds();
-        // R O Y G B I V
-        let color_r = [1.0, 0.0, 0.0, 1.0];
-        let color_o = [1.0, 0.5, 0.0, 1.0];
-        let color_y = [1.0, 1.0, 0.0, 1.0];
-        let color_g = [0.0, 1.0, 0.0, 1.0];
-        let color_gb = [0.0, 1.0, 0.5, 1.0];
-        let color_b = [0.0, 0.2, 1.0, 1.0];
-        let color_i = [0.5, 0.0, 1.0, 1.0];
-        let color_v = [0.75, 0.0, 0.5, 1.0];
-
-        let posn_center = {
-            if b.contains(cursor_position) {
-                [cursor_position.x - b.x, cursor_position.y - b.y]
-            } else {
-                [b.width / 2.0, b.height / 2.0]
-            }
-        };
 
-        let posn_tl = [0.0, 0.0];
-        let posn_t = [b.width / 2.0, 0.0];
-        let posn_tr = [b.width, 0.0];
-        let posn_r = [b.width, b.height / 2.0];
-        let posn_br = [b.width, b.height];
-        let posn_b = [(b.width / 2.0), b.height];
-        let posn_bl = [0.0, b.height];
-        let posn_l = [0.0, b.height / 2.0];
+        // The samples that exist may only cover part of the window
+        let span = self.buffersize.span(self.samples.lock().unwrap().len());
+        let left = self.x_of(b, span.start);
+        let size = Size::new(self.x_of(b, span.end) - left, b.height);
+        let mesh = self.mesh(size);
 
-        (
-            Primitive::Translate {
-                translation: Vector::new(b.x, b.y),
+        // Nothing may be analysed yet, as before recording starts, but what is marked still shows
+        let mut primitives = vec![];
+        if !mesh.indices.is_empty() {
+            primitives.push(Primitive::Translate {
+                translation: Vector::new(left, b.y),
                 content: Box::new(Primitive::Mesh2D {
-                    size: b.size(),
-                    buffers: Mesh2D {
-                        vertices: vec![
-                            Vertex2D {
-                                position: posn_center,
-                                color: [1.0, 1.0, 1.0, 1.0],
-                            },
-                            Vertex2D {
-                                position: posn_tl,
-                                color: color_r,
-                            },
-                            Vertex2D {
-                                position: posn_t,
-                                color: color_o,
-                            },
-                            Vertex2D {
-                                position: posn_tr,
-                                color: color_y,
-                            },
-                            Vertex2D {
-                                position: posn_r,
-                                color: color_g,
-                            },
-                            Vertex2D {
-                                position: posn_br,
-                                color: color_gb,
-                            },
-                            Vertex2D {
-                                position: posn_b,
-                                color: color_b,
-                            },
-                            Vertex2D {
-                                position: posn_bl,
-                                color: color_i,
-                            },
-                            Vertex2D {
-                                position: posn_l,
-                                color: color_v,
-                            },
-                        ],
-                        indices: vec![
-                            0, 1, 2, // TL
-                            0, 2, 3, // T
-                            0, 3, 4, // TR
-                            0, 4, 5, // R
-                            0, 5, 6, // BR
-                            0, 6, 7, // B
-                            0, 7, 8, // BL
-                            0, 8, 1, // L
-                        ],
-                    },
+                    size,
+                    buffers: mesh,
                 }),
-            },
+            });
+        }
+
+        // An empty selection is still drawn, as a cursor line
+        if let Some(selection) = &self.selection {
+            let start = self.x_of(b, selection.start);
+            let end = self.x_of(b, selection.end).max(start + 1.0);
+            primitives.push(strip(
+                start,
+                b.y,
+                b.height,
+                end - start,
+                Color::from_rgba(1.0, 1.0, 1.0, 0.25),
+            ));
+        }
+
+        if let Some(playhead) = self.playhead {
+            // Playback that ran to the end stops just past the last sample, which is still shown
+            let shown = self.shown(self.samples.lock().unwrap().len());
+            if (shown.start..=shown.end).contains(&playhead) {
+                let x = self.x_of(b, playhead);
+                primitives.push(strip(x - 1.0, b.y, b.height, 2.0, PLAYHEAD_COLOR));
+            }
+        }
+
+        (
+            Primitive::Group { primitives },
             mouse::Interaction::default(),
         )
     }
 }
 
-impl<'a, Message, B, T: 'a> Into<Element<'a, Message, Renderer<B>>> for Spectrogram<T>
+/// A `Spectrogram` that reports the samples selected on it with the mouse. Dragging selects a
+/// span, shift-clicking extends the current selection and double-clicking selects everything.
+/// It can also report zooming and panning with the mouse wheel.
+pub struct Interactive<'a, T, Message> {
+    spectrogram: Spectrogram<T>,
+    on_select: Box<dyn Fn(Range<usize>) -> Message + 'a>,
+    on_navigate: Option<Box<dyn Fn(Navigate) -> Message + 'a>>,
+}
+
+impl<'a, T, Message> Interactive<'a, T, Message> {
+    pub fn on_navigate(mut self, on_navigate: impl Fn(Navigate) -> Message + 'a) -> Self {
+        self.on_navigate = Some(Box::new(on_navigate));
+        self
+    }
+}
+
+impl<'a, Message, B, T> Widget<Message, Renderer<B>> for Interactive<'a, T, Message>
 where
     B: Backend,
     T: Sample,
 {
-    fn into(self) -> Element<'a, Message, Renderer<B>> {
-        Element::new(self)
+    fn width(&self) -> Length {
+        Widget::<Message, Renderer<B>>::width(&self.spectrogram)
+    }
+
+    fn height(&self) -> Length {
+        Widget::<Message, Renderer<B>>::height(&self.spectrogram)
+    }
+
+    fn layout(&self, renderer: &Renderer<B>, limits: &layout::Limits) -> layout::Node {
+        Widget::<Message, Renderer<B>>::layout(&self.spectrogram, renderer, limits)
+    }
+
+    fn hash_layout(&self, state: &mut Hasher) {
+        Widget::<Message, Renderer<B>>::hash_layout(&self.spectrogram, state)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer<B>,
+        defaults: &Defaults,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) -> (Primitive, mouse::Interaction) {
+        let (primitive, _) = Widget::<Message, Renderer<B>>::draw(
+            &self.spectrogram,
+            renderer,
+            defaults,
+            layout,
+            cursor_position,
+            viewport,
+        );
+        let interaction = if layout.bounds().contains(cursor_position) {
+            mouse::Interaction::Text
+        } else {
+            mouse::Interaction::default()
+        };
+        (primitive, interaction)
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &Renderer<B>,
+        _clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Message>,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let spectrogram = &self.spectrogram;
+        let mut interaction = spectrogram.interaction.lock().unwrap();
+
+        match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                interaction.modifiers = modifiers;
+                event::Status::Ignored
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if bounds.contains(cursor_position) =>
+            {
+                let click = mouse::Click::new(cursor_position, interaction.last_click);
+                interaction.last_click = Some(click);
+                let at = spectrogram.sample_at(bounds, cursor_position.x);
+
+                let range = match click.kind() {
+                    mouse::click::Kind::Single => {
+                        // Shift-clicking keeps the end of the selection farthest from the click
+                        let anchor = match &spectrogram.selection {
+                            Some(selection) if interaction.modifiers.shift => {
+                                if at < selection.start {
+                                    selection.end
+                                } else {
+                                    selection.start
+                                }
+                            }
+                            _ => at,
+                        };
+                        interaction.anchor = Some(anchor);
+                        anchor.min(at)..anchor.max(at)
+                    }
+                    _ => {
+                        interaction.anchor = None;
+                        0..spectrogram.samples.lock().unwrap().len()
+                    }
+                };
+                messages.push((self.on_select)(range));
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => match interaction.anchor {
+                Some(anchor) => {
+                    let at = spectrogram.sample_at(bounds, position.x);
+                    messages.push((self.on_select)(anchor.min(at)..anchor.max(at)));
+                    event::Status::Captured
+                }
+                None => event::Status::Ignored,
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if interaction.anchor.take().is_some() =>
+            {
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta })
+                if bounds.contains(cursor_position) =>
+            {
+                let on_navigate = match &self.on_navigate {
+                    Some(on_navigate) => on_navigate,
+                    None => return event::Status::Ignored,
+                };
+                let (x, y) = match delta {
+                    mouse::ScrollDelta::Lines { x, y } => (x, y),
+                    mouse::ScrollDelta::Pixels { x, y } => (x / 50.0, y / 50.0),
+                };
+
+                let modifiers = interaction.modifiers;
+                let navigate = if modifiers.is_command_pressed() {
+                    Navigate::Zoom {
+                        factor: WHEEL_ZOOM.powf(y),
+                        at: (cursor_position.x - bounds.x) / bounds.width,
+                    }
+                } else if modifiers.shift {
+                    // Some platforms turn shift+wheel into horizontal scrolling
+                    Navigate::Pan(-(x + y) * WHEEL_PAN)
+                } else if modifiers.alt {
+                    Navigate::ZoomFrequency {
+                        factor: WHEEL_ZOOM.powf(y),
+                        at: 1.0 - (cursor_position.y - bounds.y) / bounds.height,
+                    }
+                } else {
+                    return event::Status::Ignored;
+                };
+                messages.push(on_navigate(navigate));
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+}
+
+// Builds a grid of vertices over `size`, one per (time, frequency) cell, coloured by the loudest
+// value of the STFT frames of `total_bins` bins that the cell covers. Low frequencies are at the
+// bottom.
+// The samples, as floats, that the STFT frames in `frames` read: from the start of the first up
+// to the end of the last, or of the buffer.
+fn analyse<T: Sample>(samples: &[T], frames: Range<usize>, settings: &Settings) -> Vec<f32> {
+    let hop = settings.hop_size.max(1);
+    let start = (frames.start * hop).min(samples.len());
+    let end = match frames.end.checked_sub(1) {
+        Some(last) => (last * hop + settings.fft_size).min(samples.len()),
+        None => start,
+    };
+    samples[start..end.max(start)]
+        .iter()
+        .map(|s| s.to_float_sample().to_sample::<f32>())
+        .collect()
+}
+
+fn build_mesh(
+    stft: &[Vec<f32>],
+    total_bins: usize,
+    settings: &Settings,
+    frequencies: (f32, f32),
+    size: Size,
+) -> Mesh2D {
+    let frames = stft.len();
+    if frames == 0 || total_bins < 2 || size.width < 1.0 || size.height < 1.0 {
+        return Mesh2D {
+            vertices: vec![],
+            indices: vec![],
+        };
+    }
+
+    // Only the bins within the frequency band are spread over the height
+    let first_bin = ((frequencies.0 * (total_bins - 1) as f32) as usize).min(total_bins - 2);
+    let last_bin = ((frequencies.1 * (total_bins - 1) as f32).ceil() as usize)
+        .clamp(first_bin + 1, total_bins - 1);
+    let bins = last_bin - first_bin + 1;
+
+    // At least one cell, even when the mesh is narrower or shorter than a cell
+    let cols = (frames - 1).min((size.width / CELL_SIZE) as usize).max(1);
+    let rows = (bins - 1).min((size.height / CELL_SIZE) as usize).max(1);
+
+    let mut vertices = Vec::with_capacity((cols + 1) * (rows + 1));
+    for col in 0..=cols {
+        let frame_span = span(col, cols, frames);
+        let x = col as f32 * size.width / cols as f32;
+        for row in 0..=rows {
+            let bin_span = span(row, rows, bins);
+            let bin_span = first_bin + bin_span.start..first_bin + bin_span.end;
+            let db = stft[frame_span.clone()]
+                .iter()
+                .flat_map(|frame| frame[bin_span.clone()].iter())
+                .fold(f32::NEG_INFINITY, |a, b| a.max(*b));
+            vertices.push(Vertex2D {
+                position: [x, size.height - row as f32 * size.height / rows as f32],
+                color: colormap(
+                    (db - settings.db_floor) / (settings.db_ceiling - settings.db_floor),
+                ),
+            });
+        }
+    }
+
+    let mut indices = Vec::with_capacity(cols * rows * 6);
+    let stride = (rows + 1) as u32;
+    for col in 0..cols as u32 {
+        for row in 0..rows as u32 {
+            let bl = col * stride + row;
+            let br = bl + stride;
+            indices.extend_from_slice(&[bl, br, bl + 1, br, br + 1, bl + 1]);
+        }
+    }
+
+    Mesh2D { vertices, indices }
+}
+
+// The range of source indices (frames or bins) that grid line `i` of `cells` stands for.
+fn span(i: usize, cells: usize, len: usize) -> Range<usize> {
+    let start = i * (len - 1) / cells;
+    let end = ((i + 1) * (len - 1) / cells).clamp(start + 1, len);
+    start..end
+}
+
+// Maps a normalised magnitude onto a black -> purple -> orange -> yellow -> white gradient.
+fn colormap(t: f32) -> [f32; 4] {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.3, 0.0, 0.45],
+        [0.9, 0.25, 0.1],
+        [1.0, 0.85, 0.0],
+        [1.0, 1.0, 1.0],
+    ];
+
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (t as usize).min(STOPS.len() - 2);
+    let f = t - i as f32;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+
+    [
+        a[0] + (b[0] - a[0]) * f,
+        a[1] + (b[1] - a[1]) * f,
+        a[2] + (b[2] - a[2]) * f,
+        1.0,
+    ]
+}
+
+impl<'a, Message, B, T: 'a> From<Spectrogram<T>> for Element<'a, Message, Renderer<B>>
+where
+    B: Backend,
+    T: Sample,
+{
+    fn from(spectrogram: Spectrogram<T>) -> Self {
+        Element::new(spectrogram)
+    }
+}
+
+impl<'a, Message, B, T: 'a> From<Interactive<'a, T, Message>> for Element<'a, Message, Renderer<B>>
+where
+    B: Backend,
+    T: Sample,
+    Message: 'a,
+{
+    fn from(interactive: Interactive<'a, T, Message>) -> Self {
+        Element::new(interactive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_size_spans_are_clamped() {
+        assert_eq!(BufferSize::All.span(10), 0..10);
+        assert_eq!(BufferSize::Range(2, 5).span(10), 2..5);
+        assert_eq!(BufferSize::Range(8, 20).span(10), 8..10);
+        assert_eq!(BufferSize::Range(12, 20).span(10), 10..10);
+        assert_eq!(BufferSize::Range(5, 3).span(10), 5..5);
+    }
+
+    #[test]
+    fn navigating_keeps_the_view_in_the_session() {
+        let mut viewport = Viewport::default();
+        viewport.navigate(
+            Navigate::Zoom {
+                factor: 2.0,
+                at: 0.5,
+            },
+            10.0,
+        );
+        assert_eq!((viewport.zoom, viewport.start), (2.0, 2.5));
+        assert_eq!(viewport.buffersize(10.0, 100), BufferSize::Range(250, 750));
+
+        viewport.navigate(Navigate::Pan(0.5), 10.0);
+        assert_eq!(viewport.start, 5.0);
+        viewport.navigate(Navigate::Pan(0.5), 10.0);
+        assert_eq!(viewport.start, 5.0);
+        viewport.navigate(Navigate::Pan(-10.0), 10.0);
+        assert_eq!(viewport.start, 0.0);
+
+        viewport.navigate(
+            Navigate::Zoom {
+                factor: 0.1,
+                at: 0.5,
+            },
+            10.0,
+        );
+        assert_eq!((viewport.zoom, viewport.start), (1.0, 0.0));
+
+        viewport.navigate(
+            Navigate::ZoomFrequency {
+                factor: 2.0,
+                at: 0.0,
+            },
+            10.0,
+        );
+        assert_eq!(viewport.frequencies, (0.0, 0.5));
+        viewport.navigate(
+            Navigate::ZoomFrequency {
+                factor: 0.1,
+                at: 1.0,
+            },
+            10.0,
+        );
+        assert_eq!(viewport.frequencies, (0.0, 1.0));
+    }
+
+    #[test]
+    fn meshes_smaller_than_a_cell_still_build() {
+        let stft = vec![vec![-50.0; 5]];
+        let settings = Settings::default();
+        for &(width, height) in [(1.0, 1.0), (2.0, 500.0), (500.0, 2.0)].iter() {
+            let mesh = build_mesh(&stft, 5, &settings, (0.0, 1.0), Size::new(width, height));
+            assert!(!mesh.vertices.is_empty());
+        }
+    }
+
+    #[test]
+    fn views_analyse_only_the_frames_around_them() {
+        let samples: Vec<f32> = (0..20000).map(|i| (i as f32 * 0.01).sin()).collect();
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let (sender, _receiver) = std::sync::mpsc::channel();
+        let mut spectrogram = Spectrogram::new(sender).fft_size(64).hop_size(16);
+        let window = spectrogram.settings().window.coefficients(64);
+
+        // Panning within what was analysed, past it, back before it, and then recording more
+        for &(len, start, end) in [
+            (10000, 4000, 4800),
+            (10000, 4400, 6000),
+            (10000, 8000, 12000),
+            (20000, 8000, 12000),
+            (20000, 2000, 2400),
+        ]
+        .iter()
+        {
+            let grown = buffer.lock().unwrap().len();
+            buffer
+                .lock()
+                .unwrap()
+                .extend_from_slice(&samples[grown..len]);
+            spectrogram.load(buffer.clone(), BufferSize::Range(start, end));
+            spectrogram.mesh(Size::new(100.0, 100.0));
+
+            let whole = Stft::new(&samples[..len], &window, 16);
+            let analysis = spectrogram.analysis.lock().unwrap();
+            let held = analysis.first..analysis.first + analysis.stft.frames.len();
+            assert!(held.start <= start / 16 && held.end >= end.min(len).div_ceil(16));
+            assert!(held.len() < whole.frames.len() / 2);
+            assert_eq!(analysis.stft.frames[..], whole.frames[held]);
+        }
+    }
+
+    struct NullBackend;
+
+    impl Backend for NullBackend {}
+
+    // What an unanalysed spectrogram draws, other than its mesh.
+    fn draw_marks(spectrogram: &Spectrogram<f32>) -> Vec<Primitive> {
+        let node = layout::Node::new(Size::new(100.0, 50.0));
+        let (primitive, _) = Widget::<(), _>::draw(
+            spectrogram,
+            &mut Renderer::new(NullBackend),
+            &Defaults::default(),
+            Layout::new(&node),
+            Point::ORIGIN,
+            &Rectangle::with_size(Size::new(100.0, 50.0)),
+        );
+        match primitive {
+            Primitive::Group { primitives } => primitives,
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn selections_show_before_there_is_anything_to_analyse() {
+        let (sender, _receiver) = std::sync::mpsc::channel();
+        let spectrogram = Spectrogram::<f32>::new(sender).selection(Some(0..0));
+        assert_eq!(draw_marks(&spectrogram).len(), 1);
+    }
+
+    #[test]
+    fn playheads_show_before_there_is_anything_to_analyse() {
+        let (sender, _receiver) = std::sync::mpsc::channel();
+        let mut spectrogram = Spectrogram::<f32>::new(sender).playhead(Some(0));
+        assert_eq!(draw_marks(&spectrogram).len(), 1);
+
+        spectrogram.load(Arc::new(Mutex::new(vec![])), BufferSize::Range(0, 10));
+        assert_eq!(draw_marks(&spectrogram.clone().playhead(Some(10))).len(), 1);
+        assert!(draw_marks(&spectrogram.playhead(Some(11))).is_empty());
     }
 }
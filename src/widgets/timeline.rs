@@ -0,0 +1,221 @@
+use iced_graphics::{Backend, Defaults, Primitive, Renderer};
+use iced_native::{
+    event, layout, mouse, Background, Clipboard, Color, Element, Event, Font, Hasher,
+    HorizontalAlignment, Layout, Length, Point, Rectangle, Size, VerticalAlignment, Widget,
+};
+
+// The height of the ruler, in pixels.
+const HEIGHT: f32 = 28.0;
+
+// Roughly how far apart labelled ticks are, in pixels.
+const TICK_SPACING: f32 = 100.0;
+
+// Tick intervals to choose from, in seconds.
+const TICK_STEPS: [f64; 19] = [
+    0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0,
+    120.0, 300.0, 600.0,
+];
+
+const TICK_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.5);
+pub(crate) const PLAYHEAD_COLOR: Color = Color::from_rgb(1.0, 0.3, 0.2);
+
+/// The local state of a `Timeline`.
+#[derive(Debug, Default)]
+pub struct State {
+    // Whether the playhead is being dragged.
+    seeking: bool,
+}
+
+/// A time ruler drawn over the tracks, marking the play position. Clicking or dragging on it
+/// seeks.
+pub struct Timeline<'a, Message> {
+    state: &'a mut State,
+    start: f64,
+    duration: f64,
+    playhead: f64,
+    on_seek: Box<dyn Fn(f64) -> Message + 'a>,
+}
+
+impl<'a, Message> Timeline<'a, Message> {
+    /// Creates a ruler covering `duration` seconds from `start`, with the playhead at `playhead`
+    /// seconds. `on_seek` is given the time clicked on.
+    pub fn new(
+        state: &'a mut State,
+        start: f64,
+        duration: f64,
+        playhead: f64,
+        on_seek: impl Fn(f64) -> Message + 'a,
+    ) -> Self {
+        Self {
+            state,
+            start,
+            duration,
+            playhead,
+            on_seek: Box::new(on_seek),
+        }
+    }
+
+    fn time_at(&self, bounds: Rectangle, x: f32) -> f64 {
+        let t = ((x - bounds.x) / bounds.width).clamp(0.0, 1.0) as f64;
+        self.start + t * self.duration
+    }
+
+    fn x_of(&self, bounds: Rectangle, time: f64) -> f32 {
+        bounds.x + ((time - self.start) / self.duration) as f32 * bounds.width
+    }
+}
+
+impl<'a, Message, B> Widget<Message, Renderer<B>> for Timeline<'a, Message>
+where
+    B: Backend,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Units(HEIGHT as u16)
+    }
+
+    fn layout(&self, _renderer: &Renderer<B>, limits: &layout::Limits) -> layout::Node {
+        let size = limits
+            .width(Length::Fill)
+            .height(Length::Units(HEIGHT as u16))
+            .resolve(Size::ZERO);
+
+        layout::Node::new(size)
+    }
+
+    fn hash_layout(&self, _state: &mut Hasher) {}
+
+    fn draw(
+        &self,
+        _renderer: &mut Renderer<B>,
+        _defaults: &Defaults,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+    ) -> (Primitive, mouse::Interaction) {
+        let b = layout.bounds();
+        let interaction = if b.contains(cursor_position) || self.state.seeking {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        };
+        if self.duration <= 0.0 || b.width < 1.0 {
+            return (Primitive::None, interaction);
+        }
+
+        let wanted = self.duration * (TICK_SPACING / b.width) as f64;
+        let step = TICK_STEPS
+            .iter()
+            .copied()
+            .find(|step| *step >= wanted)
+            .unwrap_or(TICK_STEPS[TICK_STEPS.len() - 1]);
+
+        let mut primitives = vec![];
+        let mut tick = (self.start / step).ceil() as u64;
+        while tick as f64 * step <= self.start + self.duration {
+            let time = tick as f64 * step;
+            let x = self.x_of(b, time);
+            primitives.push(strip(
+                x,
+                b.y + b.height / 2.0,
+                b.height / 2.0,
+                1.0,
+                TICK_COLOR,
+            ));
+            primitives.push(Primitive::Text {
+                content: format_time(time, step),
+                bounds: Rectangle {
+                    x: x + 3.0,
+                    y: b.y,
+                    width: TICK_SPACING,
+                    height: b.height / 2.0,
+                },
+                color: TICK_COLOR,
+                size: 12.0,
+                font: Font::Default,
+                horizontal_alignment: HorizontalAlignment::Left,
+                vertical_alignment: VerticalAlignment::Top,
+            });
+            tick += 1;
+        }
+
+        if self.playhead >= self.start && self.playhead <= self.start + self.duration {
+            let x = self.x_of(b, self.playhead);
+            primitives.push(strip(x - 1.0, b.y, b.height, 2.0, PLAYHEAD_COLOR));
+        }
+
+        (Primitive::Group { primitives }, interaction)
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &Renderer<B>,
+        _clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Message>,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if bounds.contains(cursor_position) =>
+            {
+                self.state.seeking = true;
+                messages.push((self.on_seek)(self.time_at(bounds, cursor_position.x)));
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) if self.state.seeking => {
+                messages.push((self.on_seek)(self.time_at(bounds, position.x)));
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if self.state.seeking =>
+            {
+                self.state.seeking = false;
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+}
+
+/// A vertical strip `width` pixels wide, starting at `y`.
+pub(crate) fn strip(x: f32, y: f32, height: f32, width: f32, color: Color) -> Primitive {
+    Primitive::Quad {
+        bounds: Rectangle {
+            x,
+            y,
+            width,
+            height,
+        },
+        background: Background::Color(color),
+        border_radius: 0.0,
+        border_width: 0.0,
+        border_color: Color::TRANSPARENT,
+    }
+}
+
+// Labels a tick, with as many decimals as the tick interval needs.
+fn format_time(time: f64, step: f64) -> String {
+    if step >= 1.0 {
+        let seconds = time.round() as u64;
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        let decimals = (-step.log10()).ceil() as usize;
+        format!("{:.*}", decimals, time)
+    }
+}
+
+impl<'a, Message, B> From<Timeline<'a, Message>> for Element<'a, Message, Renderer<B>>
+where
+    B: Backend,
+    Message: 'a,
+{
+    fn from(timeline: Timeline<'a, Message>) -> Self {
+        Element::new(timeline)
+    }
+}